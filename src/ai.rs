@@ -1,56 +1,60 @@
-use std::{net::TcpStream, io::Write};
+use std::io::Write;
 
 use rand::Rng;
 
-use crate::{Ant, Ants, Turn, AntCargo, HOME_BASE_COORDINATES, utils::{get_distance, next_point}, Position, AntJob, HOME_BASE_BEACONS, cli::Args};
+use crate::{Ant, Ants, Turn, AntCargo, HOME_BASE_COORDINATES, utils::{get_distance, next_point}, Position, AntJob, HOME_BASE_BEACONS, cli::Args, ObjectKind};
 
 /// Analyzes the current game state and makes an approprate turn by moving each ant one tile.
-pub fn turn(stream: &mut TcpStream, turn: &Turn, args: &Args, ant_jobs: &[AntJob]) {
-    let mut actions: Vec<u8> = Vec::new();
+/// 
+/// The actions are written to `output` and returned, the action at index 0 is for ant 0 and so forth.
+/// All random decisions are drawn from `rng`, so that the same generator state leads to the same actions.
+pub fn turn<W: Write, R: Rng>(output: &mut W, turn: &Turn, args: &Args, ant_jobs: &[AntJob], rng: &mut R) -> [u8; 16] {
+    let mut actions = [5u8; 16];
     let ants = Ants::from_turn(turn, None, ant_jobs);
     if args.print_ants {
         ants.print_ants();
     }
     for ant in &ants.ants {
-        actions.push(ant.calc_move(turn, &ants.ant_positions, args, ant_jobs));
+        actions[ant.id as usize] = ant.calc_move(turn, &ants.ant_positions, args, ant_jobs, rng);
     }
-    match stream.write_all(&actions) {
+    match output.write_all(&actions) {
         Err(e) => println!("Error, unable to send action: {}", e),
         Ok(_ok) => (),
     }
+    actions
 }
 
 impl Ant {
     /// Decides in wich direction this ant will move in the next turn
-    fn calc_move(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob]) -> u8 {
+    fn calc_move<R: Rng>(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob], rng: &mut R) -> u8 {
         // Do nothing when dead
         if self.health == 0 {
             return 5;
         }
         // Move home when lifes <= 3
         if self.health <= 3 {
-            return self.get_direction(HOME_BASE_COORDINATES[turn.team_id as usize], ant_positions, turn);
+            return self.get_direction(HOME_BASE_COORDINATES[turn.team_id as usize], ant_positions, turn, rng);
         }
         // Move to enemy base when carrying toxin
         if self.cargo.is_some() && self.cargo.as_ref().unwrap() == &AntCargo::ToxicWaste {
-            return self.get_direction(turn.leading_team_base_coordinates(turn), ant_positions, turn);
+            return self.get_direction(turn.leading_team_base_coordinates(turn), ant_positions, turn, rng);
         }
         match self.job.unwrap() {
-            AntJob::Gatherer => self.calc_gatherer_move(turn, ant_positions, args, ant_jobs),
-            AntJob::Offensive => self.calc_offensive_move(turn, ant_positions, args, ant_jobs),
-            AntJob::WasteMover => self.calc_waste_mover_move(turn, ant_positions, args, ant_jobs),
+            AntJob::Gatherer => self.calc_gatherer_move(turn, ant_positions, args, ant_jobs, rng),
+            AntJob::Offensive => self.calc_offensive_move(turn, ant_positions, args, ant_jobs, rng),
+            AntJob::WasteMover => self.calc_waste_mover_move(turn, ant_positions, args, ant_jobs, rng),
         }
     }
 
     /// Decides in which direction the ant moves in the next turn.
     /// 
     /// This function focuses on ressource gathering.
-    fn calc_gatherer_move(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob]) -> u8 {
+    fn calc_gatherer_move<R: Rng>(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob], rng: &mut R) -> u8 {
         // Attack nearest ant with health <= 3 if hunt is enabled
         if args.hunt {
             let nearest_enemy = turn.nearest(self.pos, &turn.enemy_ants(Some(3), ant_jobs));
             if let Some(ne) = nearest_enemy {
-                return self.get_direction(ne, ant_positions, turn);
+                return self.get_direction(ne, ant_positions, turn, rng);
             }
         }
         // Move home when carrying sugar
@@ -58,14 +62,14 @@ impl Ant {
             let distance = get_distance(self.pos, HOME_BASE_COORDINATES[turn.team_id as usize]);
             // Move to beacon if to far away
             if distance > 20 {
-                return self.get_direction(HOME_BASE_BEACONS[turn.team_id as usize], ant_positions, turn);
+                return self.get_direction(HOME_BASE_BEACONS[turn.team_id as usize], ant_positions, turn, rng);
             } else {
-                return self.get_direction(HOME_BASE_COORDINATES[turn.team_id as usize], ant_positions, turn);
+                return self.get_direction(HOME_BASE_COORDINATES[turn.team_id as usize], ant_positions, turn, rng);
             }
         }
         // Search next piece of sugar
         match turn.nearest_sugar_coordinates(self.pos) {
-            Some(pos) => self.get_direction(pos, ant_positions, turn),
+            Some(pos) => self.get_direction(pos, ant_positions, turn, rng),
             None => 5,
         }
     }
@@ -73,31 +77,31 @@ impl Ant {
     /// Decides in which direction the ant moves in the next turn.
     /// 
     /// This function focuses on offensive action against enemy ants.
-    fn calc_offensive_move(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob]) -> u8 {
+    fn calc_offensive_move<R: Rng>(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob], rng: &mut R) -> u8 {
         // Attack clostest enemy ant when ant is below 5 health
         if true {
             let nearest_enemy = turn.nearest(self.pos, &turn.enemy_ants(Some(args.max_health), ant_jobs));
             if let Some(ne) = nearest_enemy {
-                return self.get_direction(ne, ant_positions, turn);
+                return self.get_direction(ne, ant_positions, turn, rng);
             }
         }
-        self.calc_gatherer_move(turn, ant_positions, args, ant_jobs)
+        self.calc_gatherer_move(turn, ant_positions, args, ant_jobs, rng)
     }
 
     /// Decides in which direction the ant movesTEAM_NAME in the next turn.
     /// 
     /// This function focuses on bring waste into enemy bases.
-    fn calc_waste_mover_move(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob]) -> u8 {
+    fn calc_waste_mover_move<R: Rng>(&self, turn: &Turn, ant_positions: &[(u16, u16)], args: &Args, ant_jobs: &[AntJob], rng: &mut R) -> u8 {
         if let Some(pos) = turn.nearest_toxic_waste_coordinates(self.pos) {
-            return self.get_direction(pos, ant_positions, turn)
+            return self.get_direction(pos, ant_positions, turn, rng)
         }
-        self.calc_offensive_move(turn, ant_positions, args, ant_jobs)
+        self.calc_offensive_move(turn, ant_positions, args, ant_jobs, rng)
     }
 
     /// Returns the direction in wich the ant should go this turn.
     /// Takes into consideration if the most optimal path is blocked by another ant and changes direction accordingly.
     /// Ants that already carry things will not walk over sugar/toxins.
-    fn get_direction(&self, target: (u16, u16), ant_positions: &[(u16, u16)], turn: &Turn) -> u8 {
+    fn get_direction<R: Rng>(&self, target: (u16, u16), ant_positions: &[(u16, u16)], turn: &Turn, rng: &mut R) -> u8 {
         let mut direction = self.move_direction(target);
         for _i in 0..9  {
             let next_pos = next_point(self.pos, direction);
@@ -107,7 +111,7 @@ impl Ant {
             if self.cargo.is_some() && !turn.object_positions().contains(&next_pos) {
                 break;
             }
-            direction = rand::thread_rng().gen_range(1..9);
+            direction = rng.gen_range(1..9);
         }
         direction
     }
@@ -141,8 +145,7 @@ impl Turn {
     fn nearest_sugar_coordinates(&self, pos: (u16, u16)) -> Option<(u16, u16)> {
         let mut sugar_pieces = Vec::new();
         for object in &self.objects {
-            let cargo = object.get_ant_cargo();
-            if cargo.is_some() && cargo.as_ref().unwrap() == &AntCargo::Sugar && !object.is_ant() {
+            if object.kind() == ObjectKind::Sugar {
                 sugar_pieces.push(object);
             }
         }
@@ -153,8 +156,7 @@ impl Turn {
     fn nearest_toxic_waste_coordinates(&self, pos: (u16, u16)) -> Option<(u16, u16)> {
         let mut toxic_waste = Vec::new();
         for object in &self.objects {
-            let cargo = object.get_ant_cargo();
-            if cargo.is_some() && cargo.as_ref().unwrap() == &AntCargo::ToxicWaste && !object.is_ant() {
+            if object.kind() == ObjectKind::ToxicWaste {
                 toxic_waste.push(object);
             }
         }
@@ -175,3 +177,88 @@ impl Turn {
     }
     
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{Ants, cli::Args, scenario::Scenario};
+
+    /// Loads the scenario and asserts the direction that `Ant::calc_move` chooses for each ant of our team.
+    ///
+    /// `flags` are passed to the client in addition to `-d`, `expected` contains pairs of ant id and direction.
+    fn assert_moves(scenario: &str, flags: &[&str], expected: &[(u8, u8)]) {
+        let scenario = Scenario::parse(scenario).unwrap();
+        let args = Args::parse_from(["tondorf_ants", "-d"].iter().chain(flags));
+        let turn = scenario.turn();
+        let ants = Ants::from_turn(turn, None, scenario.ant_jobs());
+        let moves: Vec<(u8, u8)> = ants.ants.iter()
+            .filter(|ant| ant.health > 0)
+            .map(|ant| (ant.id, ant.calc_move(turn, &ants.ant_positions, &args, scenario.ant_jobs(), &mut StdRng::seed_from_u64(0))))
+            .collect();
+        assert_eq!(moves, expected);
+    }
+
+    #[test]
+    fn test_gatherer_with_sugar_heads_to_beacon() {
+        // The base of team 0 is at 100,100 and its beacon at 110,110
+        assert_moves("
+ant G team=0 id=0 job=gatherer cargo=sugar at=100,125
+ant H team=0 id=1 job=gatherer cargo=sugar at=100,115
+grid
+", &[], &[(0, 3), (1, 2)]);
+    }
+
+    #[test]
+    fn test_gatherer_walks_to_nearest_sugar() {
+        assert_moves("
+origin 200,200
+ant G team=0 id=0 job=gatherer
+grid
+.....
+.G...
+.....
+....s
+", &[], &[(0, 9)]);
+    }
+
+    #[test]
+    fn test_weak_ant_returns_home() {
+        assert_moves("
+origin 120,120
+ant O team=0 id=4 health=3 job=offensive
+ant e team=2 id=0 health=1
+grid
+.Oe
+", &[], &[(4, 1)]);
+    }
+
+    #[test]
+    fn test_offensive_ant_attacks_weak_enemy() {
+        assert_moves("
+origin 400,400
+ant O team=0 id=0 job=offensive
+ant e team=2 id=0 health=8
+ant f team=2 id=1 health=3
+grid
+e.O..f
+", &["--max-health", "5"], &[(0, 6)]);
+    }
+
+    #[test]
+    fn test_waste_is_carried_to_leading_team() {
+        // The base of team 3 is at 700,100
+        assert_moves("
+team 3 points=50
+team 4 points=20
+origin 600,300
+ant W team=0 id=0 job=waste_mover cargo=waste
+ant M team=0 id=1 job=waste_mover
+grid
+W...
+.M..
+..w.
+", &[], &[(0, 3), (1, 9)]);
+    }
+}
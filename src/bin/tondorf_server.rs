@@ -0,0 +1,3 @@
+fn main() {
+    tondorf_ants::run_server();
+}
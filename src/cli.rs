@@ -1,29 +1,38 @@
-use std::net::Ipv4Addr;
+use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-#[derive(Parser, Debug)]
-#[command(author = "LMH01", version, about, long_about = None)]
+#[derive(Parser, Debug, Clone)]
+#[command(author = "LMH01", version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Args {
     #[arg(short, long, long_help = "The team name under wich the client should register at the server", default_value = Some("Rust_pirates"))]
     pub team_name: String,
-    #[arg(short, long, help = "The ip address of the server", default_value = "127.0.0.1")]
-    pub ip: Ipv4Addr,
+    #[arg(short, long, visible_alias = "host", help = "The host name, IPv4 or IPv6 address of the server", default_value = "127.0.0.1")]
+    pub ip: String,
     #[arg(long, help = "The port of the server", default_value = "5000")]
     pub port: u16,
+    #[arg(long, value_name = "ADDRESS", help = "Address of the server as host:port, [ipv6]:port or host, overrides --ip and --port",
+        conflicts_with = "ip")]
+    pub server: Option<String>,
+    #[arg(long, value_name = "MS", help = "Time in milliseconds after which connecting to an address of the server is given up")]
+    pub connect_timeout: Option<u64>,
+    #[arg(long, value_name = "MS", help = "Time in milliseconds without receiving data after which the connection is treated as lost")]
+    pub read_timeout: Option<u64>,
     #[arg(short, long, help = "Submit to print the players ants into console")]
     pub print_ants: bool,
+    #[arg(long, help = "Print what happened since the last turn, for example ants that died or delivered sugar and changed points")]
+    pub print_events: bool,
     #[arg(short, help = "Amount of gatherer ants, total amount of all ants needs to be 16",
         long_help = "Amount of  gatherer ants, total amount of all ants needs to be exact 16. Their top priority is to collect sugar.",
-        required_unless_present_any = ["ant_help", "default_jobs", "random_jobs"])]
+        required_unless_present_any = ["ant_help", "default_jobs", "random_jobs", "spectate"])]
     pub gatherer_ants: Option<u8>,
     #[arg(short, help = "Amount of offensive ants",
         long_help = "Amount of offensive ants. Their top priority is to attack enemy ants.",
-        required_unless_present_any = ["ant_help", "default_jobs", "random_jobs"])]
+        required_unless_present_any = ["ant_help", "default_jobs", "random_jobs", "spectate"])]
     pub offensive_ants: Option<u8>,
     #[arg(short, help = "Amount of waste mover ants",
         long_help = "Amount of waste mover ants. Their top priority is to move waste to enemy bases.",
-        required_unless_present_any = ["ant_help", "default_jobs", "random_jobs"])]
+        required_unless_present_any = ["ant_help", "default_jobs", "random_jobs", "spectate"])]
     pub waste_mover_ants: Option<u8>,
     #[arg(short, long, help = "Print extended help regarding the different ant types.", exclusive = true)]
     pub ant_help: bool,
@@ -37,4 +46,104 @@ pub struct Args {
     pub random_jobs: bool,
     #[arg(long, help = "Set to make gatherer ants hunt enemies with health <= 3. Hunting the enemy ant will take priority over delivering sugar.")]
     pub hunt: bool,
+    #[arg(long, value_name = "MAX_ATTEMPTS", help = "Connect again when the connection to the server is lost, at most MAX_ATTEMPTS times in a row. The game is over when the server closes a connection on which it sent turns, connections that are closed before the first turn are retried.")]
+    pub reconnect: Option<u32>,
+    #[arg(long, value_name = "MS", help = "Delay in milliseconds before the first reconnect attempt, doubled after each failed attempt", default_value = "500")]
+    pub reconnect_delay: u64,
+    #[arg(long, value_name = "MS", help = "Maximum delay in milliseconds between two reconnect attempts", default_value = "30000")]
+    pub reconnect_max_delay: u64,
+    #[arg(long, value_name = "PATH", help = "Record every received turn and the actions sent back into a file")]
+    pub record: Option<PathBuf>,
+    #[arg(long, value_name = "PATH", help = "Write a hex dump of every received frame and every sent action frame into a file, annotated with the decoded values")]
+    pub trace: Option<PathBuf>,
+    #[arg(long, help = "Read frames on a separate thread and only answer the newest one when the client falls behind")]
+    pub catch_up: bool,
+    #[arg(long, value_name = "MS", help = "Time in milliseconds the AI may take per turn, when it takes longer each ant repeats its last action")]
+    pub ai_budget: Option<u64>,
+    #[arg(long, help = "Stop with an error when a received turn contains inconsistent data instead of printing a warning")]
+    pub strict: bool,
+    #[arg(long, help = "Register as observer that does not control any ants, the scoreboard is printed whenever the points change. Registers with client type 0, which is an assumption and not part of the AntServer protocol, so it needs to be supported by the server like `tondorf_server` does.",
+        conflicts_with_all = ["gatherer_ants", "offensive_ants", "waste_mover_ants", "default_jobs", "random_jobs"])]
+    pub spectate: bool,
+    #[arg(long, help = "Seed for the random job selection and the random decisions of the AI, a random seed is used and printed when not set. Use the printed seed to play or replay a game with the same decisions. Recordings contain the seed unless --ai-budget was used, which makes the decisions depend on timing.")]
+    pub seed: Option<u64>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Tools that are run instead of playing a game
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Feed a recording through the AI and report where the new actions differ from the recorded ones.
+    /// 
+    /// The ant jobs from the recording are used unless they are set with -g, -o and -w, -d or -r,
+    /// the seed from the recording unless it is set with --seed.
+    Replay {
+        #[arg(help = "Path of the recording")]
+        recording: PathBuf,
+        #[arg(long, value_name = "PATH", help = "Write the new actions into this file, they are discarded otherwise")]
+        output: Option<PathBuf>,
+    },
+    /// Export turns of a recording as JSON lines, the first line describes the recording.
+    Export {
+        #[arg(help = "Path of the recording")]
+        recording: PathBuf,
+        #[arg(help = "Path of the JSON lines file that is created")]
+        output: PathBuf,
+        #[arg(long, help = "Number of the first turn that is exported, turns are counted from 0", default_value = "0")]
+        from: u64,
+        #[arg(long, help = "Number of the last turn that is exported, all remaining turns are exported if not set")]
+        to: Option<u64>,
+    },
+    /// Convert JSON lines as written by export into a recording.
+    Import {
+        #[arg(help = "Path of the JSON lines file")]
+        input: PathBuf,
+        #[arg(help = "Path of the recording that is created")]
+        output: PathBuf,
+    },
+    /// Forward a client of another team to the server and record its game.
+    /// 
+    /// The server is set with --server or --ip and --port.
+    Proxy {
+        #[arg(long, value_name = "ADDRESS", help = "Local address the client connects to", default_value = "127.0.0.1:5001")]
+        listen: String,
+        #[arg(help = "Path of the recording that is created")]
+        recording: PathBuf,
+    },
+    /// Play games between variants of the client on the simulator and rate the variants with Elo.
+    /// 
+    /// Each line of the variants file contains a name and the client arguments of a variant, for example `hunters -g 10 -o 6 -w 0 --hunt`.
+    /// Use --seed to play the same tournament again.
+    Tournament {
+        #[arg(help = "Path of the file that contains the variants")]
+        variants: PathBuf,
+        #[arg(long, help = "Number of games that are played", default_value = "100")]
+        games: u32,
+        #[arg(long, help = "Number of turns of each game", default_value = "5000")]
+        turns: u64,
+        #[arg(long, help = "Number of variants that play in each game", default_value = "2",
+            value_parser = clap::value_parser!(u8).range(2..=16))]
+        players: u8,
+        #[arg(long, value_name = "PATH", help = "Write the result of every game as JSON line into this file")]
+        results: Option<PathBuf>,
+    },
+}
+
+/// Arguments of the `tondorf_server` binary
+#[derive(Parser, Debug, Clone)]
+#[command(author = "LMH01", version, about = "Local game server that is compatible with the AntServer protocol", long_about = None)]
+pub struct ServerArgs {
+    #[arg(long, value_name = "ADDRESS", help = "Address the server listens on", default_value = "127.0.0.1:5000")]
+    pub listen: String,
+    #[arg(long, help = "Number of teams that need to join before the game starts, at most 16", default_value = "1",
+        value_parser = clap::value_parser!(u8).range(1..=16))]
+    pub players: u8,
+    #[arg(long, help = "Number of turns after which the game is over", default_value = "5000")]
+    pub turns: u64,
+    #[arg(long, value_name = "MS", help = "Time in milliseconds the clients have to answer a turn, the next turn starts earlier when all teams answered",
+        default_value = "100")]
+    pub tick: u64,
+    #[arg(long, help = "Seed for the placement of sugar, waste and ants, a random seed is used and printed when not set")]
+    pub seed: Option<u64>,
 }
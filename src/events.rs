@@ -0,0 +1,151 @@
+use std::{collections::BTreeMap, fmt::{self, Display}};
+
+use crate::{Turn, AntCargo, game::base_at};
+
+/// Something that happened between two consecutive turns.
+///
+/// Ants are identified by their team and their id inside of the team.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The ant died or disappeared from the board.
+    AntDied { team: u8, ant_id: u8, pos: (u16, u16) },
+    /// The ant lost health but is still alive.
+    AntDamaged { team: u8, ant_id: u8, health_lost: u8 },
+    /// A dead or missing ant is back on the board.
+    AntRespawned { team: u8, ant_id: u8, pos: (u16, u16) },
+    /// The ant picked up a piece of sugar.
+    SugarPickedUp { team: u8, ant_id: u8, pos: (u16, u16) },
+    /// The ant no longer carries its sugar, ants can only drop sugar in their own base.
+    SugarDelivered { team: u8, ant_id: u8, pos: (u16, u16) },
+    /// The ant dropped its toxic waste, `base` is the home base it was dropped in or `None` if it was dropped outside of all bases.
+    WasteDropped { team: u8, ant_id: u8, pos: (u16, u16), base: Option<usize> },
+    /// The points of the team changed.
+    PointsChanged { team: u8, old: u16, new: u16 },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::AntDied { team, ant_id, pos } => write!(f, "ant {} of team {} died at {:?}", ant_id, team, pos),
+            Event::AntDamaged { team, ant_id, health_lost } => write!(f, "ant {} of team {} lost {} health", ant_id, team, health_lost),
+            Event::AntRespawned { team, ant_id, pos } => write!(f, "ant {} of team {} respawned at {:?}", ant_id, team, pos),
+            Event::SugarPickedUp { team, ant_id, pos } => write!(f, "ant {} of team {} picked up sugar at {:?}", ant_id, team, pos),
+            Event::SugarDelivered { team, ant_id, pos } => write!(f, "ant {} of team {} delivered sugar at {:?}", ant_id, team, pos),
+            Event::WasteDropped { team, ant_id, pos, base: Some(base) } => write!(f, "ant {} of team {} dropped toxic waste at {:?} in base {}", ant_id, team, pos, base),
+            Event::WasteDropped { team, ant_id, pos, base: None } => write!(f, "ant {} of team {} dropped toxic waste at {:?}", ant_id, team, pos),
+            Event::PointsChanged { team, old, new } => write!(f, "team {} points changed from {} to {}", team, old, new),
+        }
+    }
+}
+
+/// Position, health and cargo of an ant in a single turn
+struct AntState {
+    pos: (u16, u16),
+    health: u8,
+    cargo: Option<AntCargo>,
+}
+
+/// Collects the ants of the turn by team and ant id.
+fn ants(turn: &Turn) -> BTreeMap<(u8, u8), AntState> {
+    turn.objects.iter()
+        .filter(|object| object.kind().is_ant())
+        .map(|object| ((object.b1.lower, object.b2.upper), AntState { pos: object.pos, health: object.b2.lower, cargo: object.kind().cargo() }))
+        .collect()
+}
+
+/// Compares two consecutive turns and returns what happened in between.
+///
+/// Ant events are ordered by team and ant id, point changes follow at the end.
+pub fn diff(previous: &Turn, current: &Turn) -> Vec<Event> {
+    let mut events = Vec::new();
+    let previous_ants = ants(previous);
+    let current_ants = ants(current);
+    let mut keys: Vec<&(u8, u8)> = previous_ants.keys().chain(current_ants.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for &(team, ant_id) in keys {
+        let before = previous_ants.get(&(team, ant_id)).filter(|ant| ant.health > 0);
+        let after = current_ants.get(&(team, ant_id)).filter(|ant| ant.health > 0);
+        let (before, after) = match (before, after) {
+            (Some(before), Some(after)) => (before, after),
+            (Some(before), None) => {
+                events.push(Event::AntDied { team, ant_id, pos: before.pos });
+                continue;
+            }
+            (None, Some(after)) => {
+                events.push(Event::AntRespawned { team, ant_id, pos: after.pos });
+                continue;
+            }
+            (None, None) => continue,
+        };
+        if after.health < before.health {
+            events.push(Event::AntDamaged { team, ant_id, health_lost: before.health - after.health });
+        }
+        match (&before.cargo, &after.cargo) {
+            (None, Some(AntCargo::Sugar)) => events.push(Event::SugarPickedUp { team, ant_id, pos: after.pos }),
+            (Some(AntCargo::Sugar), None) => events.push(Event::SugarDelivered { team, ant_id, pos: after.pos }),
+            (Some(AntCargo::ToxicWaste), None) => events.push(Event::WasteDropped { team, ant_id, pos: after.pos, base: base_at(after.pos) }),
+            _ => (),
+        }
+    }
+    for (old, new) in previous.teams.iter().zip(&current.teams) {
+        if old.points != new.points {
+            events.push(Event::PointsChanged { team: new.id as u8, old: old.points, new: new.points });
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Turn, Object, Pair};
+
+    use super::{diff, Event};
+
+    /// Creates a turn in which team 1 has `points` and the ants are described by (kind, team, ant id, health, x, y).
+    fn turn(points: u16, ants: &[(u8, u8, u8, u8, u16, u16)]) -> Turn {
+        Turn::test(0, ants.iter().map(|&(kind, team, ant_id, health, x, y)| Object {
+            b1: Pair { upper: kind, lower: team },
+            b2: Pair { upper: ant_id, lower: health },
+            pos: (x, y),
+        }).collect()).with_teams(|team| if team.id == 1 { team.points = points })
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = turn(10, &[
+            (1, 0, 0, 10, 200, 200),
+            (1, 0, 1, 10, 210, 210),
+            (3, 1, 0, 10, 305, 105),
+            (5, 1, 1, 10, 502, 98),
+            (1, 1, 2, 0, 0, 0),
+            (1, 2, 3, 2, 400, 400),
+        ]);
+        let current = turn(11, &[
+            (3, 0, 0, 10, 201, 201),
+            (1, 0, 1, 7, 210, 211),
+            (1, 1, 0, 10, 304, 104),
+            (1, 1, 1, 10, 501, 99),
+            (1, 1, 2, 10, 300, 100),
+        ]);
+        assert_eq!(diff(&previous, &current), vec![
+            Event::SugarPickedUp { team: 0, ant_id: 0, pos: (201, 201) },
+            Event::AntDamaged { team: 0, ant_id: 1, health_lost: 3 },
+            Event::SugarDelivered { team: 1, ant_id: 0, pos: (304, 104) },
+            Event::WasteDropped { team: 1, ant_id: 1, pos: (501, 99), base: Some(2) },
+            Event::AntRespawned { team: 1, ant_id: 2, pos: (300, 100) },
+            Event::AntDied { team: 2, ant_id: 3, pos: (400, 400) },
+            Event::PointsChanged { team: 1, old: 10, new: 11 },
+        ]);
+        assert_eq!(diff(&current, &current), Vec::new());
+        assert_eq!(Event::WasteDropped { team: 1, ant_id: 1, pos: (501, 99), base: Some(2) }.to_string(), "ant 1 of team 1 dropped toxic waste at (501, 99) in base 2");
+    }
+
+    #[test]
+    fn test_waste_dropped_outside_of_bases() {
+        let previous = turn(0, &[(5, 3, 4, 10, 400, 600)]);
+        let current = turn(0, &[(1, 3, 4, 10, 401, 600)]);
+        assert_eq!(diff(&previous, &current), vec![Event::WasteDropped { team: 3, ant_id: 4, pos: (401, 600), base: None }]);
+        assert_eq!(diff(&previous, &current)[0].to_string(), "ant 4 of team 3 dropped toxic waste at (401, 600)");
+    }
+}
@@ -0,0 +1,386 @@
+//! Rules of the game as they are implemented by the local server:
+//!
+//! - Every team starts with 16 ants with full health around its home base.
+//! - Each turn every ant moves one step in the direction of its action (see `utils::next_point`),
+//!   moves that leave the board or end on another living ant are not executed.
+//! - An ant without cargo picks up sugar or toxic waste it is standing on.
+//! - Each ant loses one health for every enemy ant next to it, ants without health die and drop their cargo.
+//! - Ants in their own base regain one health per turn.
+//! - Sugar that is carried into the own base earns one point, a new piece of sugar appears on the board.
+//! - Toxic waste that is carried into any base costs the team of that base one point, new toxic waste appears on the board.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{Turn, Team, Object, Pair, AntCargo, ObjectKind, HOME_BASE_COORDINATES, network::TeamName, utils::get_distance};
+
+/// Width and height of the board, all coordinates are below this value
+pub const BOARD_SIZE: u16 = 1000;
+/// Health of an ant that was just spawned, ants don't gain health above this value
+pub const MAX_HEALTH: u8 = 10;
+/// Ants that are at most this far away from the coordinates of a home base are inside of the base
+const BASE_RADIUS: u16 = 10;
+/// Number of sugar pieces on the board
+const SUGAR_COUNT: usize = 100;
+/// Number of toxic waste objects on the board
+const WASTE_COUNT: usize = 20;
+
+/// An ant on the board
+#[derive(Debug)]
+struct GameAnt {
+    id: u8,
+    pos: (u16, u16),
+    health: u8,
+    cargo: Option<AntCargo>,
+}
+
+/// A team that has joined the game
+#[derive(Debug)]
+struct GameTeam {
+    name: TeamName,
+    points: u16,
+    /// Living ants, dead ants are removed
+    ants: Vec<GameAnt>,
+}
+
+/// State of a running game.
+#[derive(Debug)]
+pub struct Game {
+    /// The teams by their id, free slots are `None`
+    teams: Vec<Option<GameTeam>>,
+    sugar: Vec<(u16, u16)>,
+    waste: Vec<(u16, u16)>,
+    rng: StdRng,
+}
+
+impl Game {
+
+    /// Creates a game without teams in which sugar and toxic waste are placed randomly.
+    ///
+    /// Games with the same seed place sugar, waste and ants at the same positions when the teams make the same moves.
+    pub fn new(seed: u64) -> Self {
+        let mut game = Self {
+            teams: (0..16).map(|_| None).collect(),
+            sugar: Vec::new(),
+            waste: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+        };
+        for _ in 0..SUGAR_COUNT {
+            let pos = game.free_position();
+            game.sugar.push(pos);
+        }
+        for _ in 0..WASTE_COUNT {
+            let pos = game.free_position();
+            game.waste.push(pos);
+        }
+        game
+    }
+
+    /// Adds a team to the first free slot and places its ants around its base.
+    ///
+    /// Returns the id of the team or `None` when all 16 slots are taken.
+    pub fn join(&mut self, name: TeamName) -> Option<usize> {
+        let id = self.teams.iter().position(Option::is_none)?;
+        self.join_at(id, name);
+        Some(id)
+    }
+
+    /// Adds a team to the slot with the id, so that it plays from the base with the same id.
+    ///
+    /// Returns `false` when the id is not valid or the slot is already taken.
+    pub fn join_at(&mut self, id: usize, name: TeamName) -> bool {
+        if !matches!(self.teams.get(id), Some(None)) {
+            return false;
+        }
+        let base = HOME_BASE_COORDINATES[id];
+        let ants = (0..16u8).map(|ant_id| GameAnt {
+            id: ant_id,
+            pos: (base.0 + u16::from(ant_id % 4) - 1, base.1 + u16::from(ant_id / 4) - 1),
+            health: MAX_HEALTH,
+            cargo: None,
+        }).collect();
+        self.teams[id] = Some(GameTeam { name, points: 0, ants });
+        true
+    }
+
+    /// Builds the turn that is sent to the team with the id.
+    pub fn turn(&self, team_id: usize) -> Turn {
+        let teams = self.teams.iter().enumerate().map(|(id, team)| match team {
+            Some(team) => Team { id: id as i16, points: team.points, remaining_ants: team.ants.len() as u16, team_name: team.name.clone() },
+            None => Team { id: id as i16, points: 0, remaining_ants: 0, team_name: TeamName::from_bytes(&[]) },
+        }).collect();
+        let mut objects = Vec::new();
+        for (id, team) in self.teams.iter().enumerate() {
+            for ant in team.iter().flat_map(|team| &team.ants) {
+                let kind = ObjectKind::ant(ant.cargo.as_ref()).to_nibble();
+                objects.push(Object { b1: Pair { upper: kind, lower: id as u8 }, b2: Pair { upper: ant.id, lower: ant.health }, pos: ant.pos });
+            }
+        }
+        for (kind, positions) in [(ObjectKind::Sugar, &self.sugar), (ObjectKind::ToxicWaste, &self.waste)] {
+            for pos in positions {
+                objects.push(Object { b1: Pair { upper: kind.to_nibble(), lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: *pos });
+            }
+        }
+        Turn {
+            team_id: team_id as i16,
+            teams,
+            _nr_of_objects: objects.len() as u16,
+            objects,
+        }
+    }
+
+    /// Plays one turn with the actions of all teams, actions are indexed by team id and ant id.
+    pub fn step(&mut self, actions: &[[u8; 16]; 16]) {
+        self.move_ants(actions);
+        self.pick_up();
+        self.fight();
+        self.visit_bases();
+    }
+
+    /// Moves every ant in the order of team and ant id.
+    fn move_ants(&mut self, actions: &[[u8; 16]; 16]) {
+        for (id, team_actions) in actions.iter().enumerate() {
+            let nr_of_ants = self.teams[id].as_ref().map_or(0, |team| team.ants.len());
+            for index in 0..nr_of_ants {
+                let ant = &self.teams[id].as_ref().unwrap().ants[index];
+                let target = match step_target(ant.pos, team_actions[usize::from(ant.id)]) {
+                    Some(target) if !self.is_ant_at(target) => target,
+                    _ => continue,
+                };
+                self.teams[id].as_mut().unwrap().ants[index].pos = target;
+            }
+        }
+    }
+
+    /// Lets ants without cargo pick up sugar or toxic waste at their position.
+    fn pick_up(&mut self) {
+        for ant in self.teams.iter_mut().flatten().flat_map(|team| &mut team.ants) {
+            if ant.cargo.is_some() {
+                continue;
+            }
+            if let Some(index) = self.sugar.iter().position(|pos| *pos == ant.pos) {
+                self.sugar.swap_remove(index);
+                ant.cargo = Some(AntCargo::Sugar);
+            } else if let Some(index) = self.waste.iter().position(|pos| *pos == ant.pos) {
+                self.waste.swap_remove(index);
+                ant.cargo = Some(AntCargo::ToxicWaste);
+            }
+        }
+    }
+
+    /// Damages all ants that are next to enemy ants at the same time, dead ants drop their cargo.
+    fn fight(&mut self) {
+        let positions: Vec<(usize, (u16, u16))> = self.teams.iter().enumerate()
+            .flat_map(|(id, team)| team.iter().flat_map(|team| &team.ants).map(move |ant| (id, ant.pos)))
+            .collect();
+        for (id, team) in self.teams.iter_mut().enumerate() {
+            let team = match team {
+                Some(team) => team,
+                None => continue,
+            };
+            for ant in &mut team.ants {
+                let enemies = positions.iter()
+                    .filter(|(enemy_id, pos)| *enemy_id != id && pos.0.abs_diff(ant.pos.0) <= 1 && pos.1.abs_diff(ant.pos.1) <= 1)
+                    .count();
+                ant.health = ant.health.saturating_sub(enemies.min(usize::from(u8::MAX)) as u8);
+            }
+            for ant in team.ants.iter().filter(|ant| ant.health == 0) {
+                match ant.cargo {
+                    Some(AntCargo::Sugar) => self.sugar.push(ant.pos),
+                    Some(AntCargo::ToxicWaste) => self.waste.push(ant.pos),
+                    None => (),
+                }
+            }
+            team.ants.retain(|ant| ant.health > 0);
+        }
+    }
+
+    /// Heals ants in their own base and handles sugar and toxic waste that is carried into a base.
+    fn visit_bases(&mut self) {
+        for id in 0..16 {
+            let nr_of_ants = self.teams[id].as_ref().map_or(0, |team| team.ants.len());
+            for index in 0..nr_of_ants {
+                let team = self.teams[id].as_mut().unwrap();
+                let ant = &mut team.ants[index];
+                let base = match base_at(ant.pos) {
+                    Some(base) => base,
+                    None => continue,
+                };
+                if base == id {
+                    ant.health = (ant.health + 1).min(MAX_HEALTH);
+                }
+                match ant.cargo {
+                    Some(AntCargo::Sugar) if base == id => {
+                        ant.cargo = None;
+                        team.points = team.points.saturating_add(1);
+                        let pos = self.free_position();
+                        self.sugar.push(pos);
+                    }
+                    Some(AntCargo::ToxicWaste) => {
+                        ant.cargo = None;
+                        if let Some(team) = &mut self.teams[base] {
+                            team.points = team.points.saturating_sub(1);
+                        }
+                        let pos = self.free_position();
+                        self.waste.push(pos);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Returns true if a living ant is at the position.
+    fn is_ant_at(&self, pos: (u16, u16)) -> bool {
+        self.teams.iter().flatten().flat_map(|team| &team.ants).any(|ant| ant.pos == pos)
+    }
+
+    /// Returns a random position outside of the bases that is not occupied by sugar or toxic waste.
+    fn free_position(&mut self) -> (u16, u16) {
+        loop {
+            let pos = (self.rng.gen_range(0..BOARD_SIZE), self.rng.gen_range(0..BOARD_SIZE));
+            if base_at(pos).is_none() && !self.sugar.contains(&pos) && !self.waste.contains(&pos) {
+                return pos;
+            }
+        }
+    }
+}
+
+/// Returns the position an ant at `pos` reaches with the action or `None` if it does not move.
+fn step_target(pos: (u16, u16), action: u8) -> Option<(u16, u16)> {
+    if !(1..=9).contains(&action) || action == 5 {
+        return None;
+    }
+    let dx = i32::from((action - 1) % 3) - 1;
+    let dy = i32::from((action - 1) / 3) - 1;
+    let x = u16::try_from(i32::from(pos.0) + dx).ok().filter(|x| *x < BOARD_SIZE)?;
+    let y = u16::try_from(i32::from(pos.1) + dy).ok().filter(|y| *y < BOARD_SIZE)?;
+    Some((x, y))
+}
+
+/// Returns the id of the base the position is in.
+pub(crate) fn base_at(pos: (u16, u16)) -> Option<usize> {
+    HOME_BASE_COORDINATES.iter().position(|base| get_distance(pos, *base) <= BASE_RADIUS)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AntCargo, ObjectKind, HOME_BASE_COORDINATES, network::TeamName, utils::next_point, validation::validate};
+
+    use super::{Game, GameAnt, MAX_HEALTH, step_target};
+
+    /// Creates a game without sugar and toxic waste in which teams 0 and 1 have joined.
+    fn game() -> Game {
+        let mut game = Game::new(1);
+        game.sugar.clear();
+        game.waste.clear();
+        assert_eq!(game.join(TeamName::new("first").unwrap()), Some(0));
+        assert_eq!(game.join(TeamName::new("second").unwrap()), Some(1));
+        game
+    }
+
+    /// Replaces the ants of the team.
+    fn place(game: &mut Game, team: usize, ants: Vec<GameAnt>) {
+        game.teams[team].as_mut().unwrap().ants = ants;
+    }
+
+    #[test]
+    fn test_step_target() {
+        for action in [1, 2, 3, 4, 6, 7, 8, 9] {
+            assert_eq!(step_target((500, 500), action), Some(next_point((500, 500), action)));
+        }
+        assert_eq!(step_target((500, 500), 5), None);
+        assert_eq!(step_target((500, 500), 0), None);
+        assert_eq!(step_target((0, 500), 4), None);
+        assert_eq!(step_target((500, 999), 8), None);
+    }
+
+    #[test]
+    fn test_join_and_turn() {
+        let mut game = Game::new(1);
+        for i in 0..16 {
+            assert_eq!(game.join(TeamName::new(&format!("team{}", i)).unwrap()), Some(i));
+        }
+        assert_eq!(game.join(TeamName::new("late").unwrap()), None);
+        let turn = game.turn(3);
+        assert_eq!(turn.team_id, 3);
+        assert_eq!(turn.teams[3].remaining_ants, 16);
+        assert_eq!(turn.objects.len(), 16 * 16 + 100 + 20);
+        assert_eq!(validate(&turn), Vec::new());
+        assert_eq!(crate::Turn::from_bytes(&turn.to_bytes()).unwrap(), turn);
+    }
+
+    #[test]
+    fn test_turn_object_kinds() {
+        let mut game = game();
+        place(&mut game, 0, Vec::new());
+        place(&mut game, 1, vec![
+            GameAnt { id: 0, pos: (400, 400), health: MAX_HEALTH, cargo: None },
+            GameAnt { id: 1, pos: (410, 400), health: MAX_HEALTH, cargo: Some(AntCargo::Sugar) },
+            GameAnt { id: 2, pos: (420, 400), health: MAX_HEALTH, cargo: Some(AntCargo::ToxicWaste) },
+        ]);
+        game.sugar.push((430, 400));
+        game.waste.push((440, 400));
+        let turn = crate::Turn::from_bytes(&game.turn(1).to_bytes()).unwrap();
+        let kinds: Vec<((u16, u16), ObjectKind)> = turn.objects.iter().map(|object| (object.pos, object.kind())).collect();
+        assert_eq!(kinds, vec![
+            ((400, 400), ObjectKind::Ant),
+            ((410, 400), ObjectKind::AntWithSugar),
+            ((420, 400), ObjectKind::AntWithToxicWaste),
+            ((430, 400), ObjectKind::Sugar),
+            ((440, 400), ObjectKind::ToxicWaste),
+        ]);
+    }
+
+    #[test]
+    fn test_moves_and_sugar() {
+        let mut game = game();
+        let base = HOME_BASE_COORDINATES[0];
+        place(&mut game, 0, vec![
+            GameAnt { id: 0, pos: (base.0 + 9, base.1), health: MAX_HEALTH, cargo: Some(AntCargo::Sugar) },
+            GameAnt { id: 1, pos: (400, 400), health: MAX_HEALTH, cargo: None },
+            GameAnt { id: 2, pos: (402, 400), health: MAX_HEALTH, cargo: None },
+        ]);
+        place(&mut game, 1, Vec::new());
+        game.sugar.push((401, 400));
+        let mut actions = [[5; 16]; 16];
+        actions[0][0] = 4;
+        actions[0][1] = 6;
+        actions[0][2] = 4;
+        game.step(&actions);
+        let team = game.teams[0].as_ref().unwrap();
+        assert_eq!(team.points, 1);
+        assert_eq!(team.ants[0].cargo, None);
+        assert_eq!(team.ants[1].pos, (401, 400));
+        assert_eq!(team.ants[1].cargo, Some(AntCargo::Sugar));
+        // The third ant stays where it is, because the second ant moved onto the cell it wanted to move to
+        assert_eq!(team.ants[2].pos, (402, 400));
+        // A new piece of sugar was placed for the delivered one
+        assert_eq!(game.sugar.len(), 1);
+    }
+
+    #[test]
+    fn test_fights_and_waste() {
+        let mut game = game();
+        place(&mut game, 0, vec![
+            GameAnt { id: 0, pos: (500, 500), health: 1, cargo: Some(AntCargo::Sugar) },
+            GameAnt { id: 1, pos: (600, 600), health: 5, cargo: None },
+        ]);
+        let own_base = HOME_BASE_COORDINATES[1];
+        place(&mut game, 1, vec![
+            GameAnt { id: 0, pos: (501, 501), health: 5, cargo: None },
+            GameAnt { id: 1, pos: (499, 500), health: 5, cargo: None },
+            GameAnt { id: 2, pos: (own_base.0 + 2, own_base.1), health: 4, cargo: Some(AntCargo::ToxicWaste) },
+        ]);
+        game.teams[1].as_mut().unwrap().points = 3;
+        game.step(&[[5; 16]; 16]);
+        let first = game.teams[0].as_ref().unwrap();
+        assert_eq!(first.ants.len(), 1);
+        assert_eq!(game.sugar, vec![(500, 500)]);
+        let second = game.teams[1].as_ref().unwrap();
+        assert_eq!(second.ants.iter().map(|ant| ant.health).collect::<Vec<u8>>(), vec![4, 4, 5]);
+        assert_eq!(second.ants[2].cargo, None);
+        assert_eq!(second.points, 2);
+        assert_eq!(game.waste.len(), 1);
+        assert_eq!(game.turn(0).teams[0].remaining_ants, 1);
+    }
+}
@@ -0,0 +1,246 @@
+//! The first line of an export describes the recording, every following line contains one turn:
+//!
+//! ```text
+//! {"client_version":"0.1.0","team_name":"Rust_pirates","jobs":["Gatherer",...,null],"seed":42}
+//! {"turn":0,"timestamp":1676000000000,"team_id":3,"teams":[{"id":0,"name":"ants","points":0,"remaining_ants":16},...],
+//!  "objects":[{"kind":1,"team":3,"ant_id":0,"health":10,"x":100,"y":120},...],"actions":[5,9,...]}
+//! ```
+//!
+//! Objects are stored with the four 4 bit values of the protocol, so that every frame can be restored exactly:
+//! `kind` is the object type (see `ObjectKind`), `team` the team id, `ant_id` and `health` the second byte.
+
+use std::io::{BufRead, Read, Seek, Write};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{AntJob, Turn, Team, Object, Pair, network::{TurnView, TeamName, ProtocolError}, recording::{RecordingReader, Recorder, RecordingHeader, RecordedTurn}};
+
+/// First line of an export
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct HeaderLine {
+    client_version: String,
+    team_name: String,
+    jobs: [Option<AntJob>; 16],
+    /// Missing in exports of recordings without a seed
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+/// A turn of the recording
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TurnLine {
+    /// Number of the turn in the recording, ignored on import
+    turn: u64,
+    timestamp: u64,
+    team_id: i16,
+    teams: Vec<TeamLine>,
+    objects: Vec<ObjectLine>,
+    actions: [u8; 16],
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TeamLine {
+    id: i16,
+    name: String,
+    points: u16,
+    remaining_ants: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ObjectLine {
+    kind: u8,
+    team: u8,
+    ant_id: u8,
+    health: u8,
+    x: u16,
+    y: u16,
+}
+
+/// Writes the turns `from..=to` of the recording as JSON lines.
+///
+/// Turns whose frame can not be decoded are skipped. Returns the number of turns that were written.
+pub fn export<R: Read + Seek, W: Write>(reader: &mut RecordingReader<R>, output: &mut W, from: u64, to: Option<u64>) -> Result<u64, ProtocolError> {
+    let header = reader.header();
+    let header = HeaderLine {
+        client_version: header.client_version.clone(),
+        team_name: header.team_name.to_string(),
+        jobs: header.jobs,
+        seed: header.seed,
+    };
+    write_line(output, &header)?;
+    let mut written = 0;
+    if !reader.seek_to_turn(from)? {
+        return Ok(written);
+    }
+    let mut nr = from;
+    let last = to.unwrap_or(u64::MAX);
+    while nr <= last {
+        let recorded = match reader.next_turn()? {
+            Some(recorded) => recorded,
+            None => break,
+        };
+        match TurnView::new(&recorded.frame) {
+            Ok(view) => {
+                write_line(output, &TurnLine {
+                    turn: nr,
+                    timestamp: recorded.timestamp,
+                    team_id: view.team_id(),
+                    teams: view.teams().map(|team| TeamLine {
+                        id: team.id(),
+                        name: TeamName::from_bytes(team.name_bytes()).to_string(),
+                        points: team.points(),
+                        remaining_ants: team.remaining_ants(),
+                    }).collect(),
+                    objects: view.objects().map(|object| ObjectLine {
+                        kind: object.b1.upper,
+                        team: object.b1.lower,
+                        ant_id: object.b2.upper,
+                        health: object.b2.lower,
+                        x: object.pos.0,
+                        y: object.pos.1,
+                    }).collect(),
+                    actions: recorded.actions,
+                })?;
+                written += 1;
+            }
+            Err(e) => println!("Turn {}: skipped: {}", nr, e),
+        }
+        nr += 1;
+    }
+    Ok(written)
+}
+
+/// Reads JSON lines as written by [`export`] and writes them into a recording.
+///
+/// Returns the number of turns that were written.
+pub fn import<R: BufRead, W: Write + Seek>(input: R, output: W) -> Result<u64, ProtocolError> {
+    let mut lines = input.lines().enumerate().filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()));
+    let header: HeaderLine = match lines.next() {
+        Some((nr, line)) => parse_line(nr, &line?)?,
+        None => return Err(ProtocolError::UnexpectedEof),
+    };
+    let header = RecordingHeader {
+        client_version: header.client_version,
+        team_name: TeamName::new(&header.team_name)?,
+        jobs: header.jobs,
+        seed: header.seed,
+    };
+    let mut recorder = Recorder::new(output, &header)?;
+    let mut written = 0;
+    for (nr, line) in lines {
+        let line: TurnLine = parse_line(nr, &line?)?;
+        let turn = line.to_turn()?;
+        recorder.record(&RecordedTurn {
+            timestamp: line.timestamp,
+            frame: turn.to_bytes(),
+            actions: line.actions,
+        })?;
+        written += 1;
+    }
+    recorder.finish()?;
+    Ok(written)
+}
+
+impl TurnLine {
+
+    /// Builds the turn described by this line.
+    fn to_turn(&self) -> Result<Turn, ProtocolError> {
+        if self.teams.len() != 16 {
+            return Err(ProtocolError::OutOfRange { field: "number of teams", value: self.teams.len() as i64 });
+        }
+        let mut teams = Vec::new();
+        for team in &self.teams {
+            teams.push(Team {
+                id: team.id,
+                points: team.points,
+                remaining_ants: team.remaining_ants,
+                team_name: TeamName::new(&team.name)?,
+            });
+        }
+        let mut objects = Vec::new();
+        for object in &self.objects {
+            objects.push(Object {
+                b1: Pair { upper: nibble("kind", object.kind)?, lower: nibble("team", object.team)? },
+                b2: Pair { upper: nibble("ant_id", object.ant_id)?, lower: nibble("health", object.health)? },
+                pos: (object.x, object.y),
+            });
+        }
+        Ok(Turn {
+            team_id: self.team_id,
+            teams,
+            _nr_of_objects: objects.len() as u16,
+            objects,
+        })
+    }
+}
+
+/// Checks that the value fits into 4 bits.
+fn nibble(field: &'static str, value: u8) -> Result<u8, ProtocolError> {
+    if value > 15 {
+        return Err(ProtocolError::OutOfRange { field, value: i64::from(value) });
+    }
+    Ok(value)
+}
+
+/// Writes the value as a single line of JSON.
+fn write_line<W: Write, T: Serialize>(output: &mut W, value: &T) -> Result<(), ProtocolError> {
+    serde_json::to_writer(&mut *output, value).map_err(|e| ProtocolError::Io(e.into()))?;
+    output.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Parses the line with the number `nr` (counted from 0).
+fn parse_line<'a, T: Deserialize<'a>>(nr: usize, line: &'a str) -> Result<T, ProtocolError> {
+    serde_json::from_str(line).map_err(|e| ProtocolError::Malformed { field: "json line", reason: format!("line {}: {}", nr + 1, e) })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{AntJob, Turn, Object, Pair, network::TeamName, recording::{Recorder, RecordingHeader, RecordedTurn, RecordingReader}};
+
+    use super::{export, import};
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let header = RecordingHeader::new(TeamName::new("Rust_pirates").unwrap(), &[AntJob::Offensive; 16], Some(7));
+        let mut recorder = Recorder::new(Cursor::new(Vec::new()), &header).unwrap();
+        let mut turns = Vec::new();
+        for i in 0..5u16 {
+            let turn = Turn::test(3, vec![
+                Object { b1: Pair { upper: 3, lower: 3 }, b2: Pair { upper: 15, lower: 7 }, pos: (100 + i, 120) },
+                Object { b1: Pair { upper: 9, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (500, 500) },
+            ]).with_teams(|team| {
+                team.points = team.id as u16 + i;
+                team.team_name = TeamName::new("ÄÖÜ").unwrap();
+            });
+            let recorded = RecordedTurn { timestamp: u64::from(i) * 100, frame: turn.to_bytes(), actions: [i as u8; 16] };
+            recorder.record(&recorded).unwrap();
+            turns.push(recorded);
+        }
+        let recording = recorder.finish().unwrap().into_inner();
+
+        let mut json = Vec::new();
+        let written = export(&mut RecordingReader::new(Cursor::new(&recording)).unwrap(), &mut json, 1, Some(3)).unwrap();
+        assert_eq!(written, 3);
+        let text = String::from_utf8(json.clone()).unwrap();
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.lines().next().unwrap().contains(r#""team_name":"Rust_pirates""#));
+        assert!(text.lines().next().unwrap().ends_with(r#""seed":7}"#));
+        assert!(text.lines().nth(1).unwrap().starts_with(r#"{"turn":1,"timestamp":100,"team_id":3"#));
+        assert!(text.contains(r#"{"kind":3,"team":3,"ant_id":15,"health":7,"x":103,"y":120}"#));
+
+        let mut imported = Cursor::new(Vec::new());
+        assert_eq!(import(Cursor::new(json), &mut imported).unwrap(), 3);
+        let mut reader = RecordingReader::new(Cursor::new(imported.into_inner())).unwrap();
+        assert_eq!(reader.header(), &header);
+        for turn in &turns[1..4] {
+            assert_eq!(reader.next_turn().unwrap().as_ref(), Some(turn));
+        }
+        assert_eq!(reader.next_turn().unwrap(), None);
+
+        let invalid = Cursor::new(format!("{}\n{{\"turn\":0}}\n", text.lines().next().unwrap()));
+        assert!(import(invalid, Cursor::new(Vec::new())).unwrap_err().to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,1020 @@
+use std::{net::{TcpStream, TcpListener}, io::{self, BufReader, BufWriter, Write}, collections::HashSet, process::exit, thread::sleep, fs::{self, File}, time::{Duration, Instant}};
+
+use cli::Command;
+
+use clap::Parser;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use serde::{Serialize, Deserialize};
+
+use crate::{network::{Register, ProtocolError, TeamName, ReconnectPolicy, FrameReader, connect, read_frame}, ai::turn, summary::{GameSummary, Scoreboard},
+    recording::{Recorder, RecordingHeader, RecordedTurn, RecordingReader, NO_ACTIONS, timestamp_now}, replay::replay,
+    json_lines::{export, import}, proxy::proxy, tournament::{parse_variants, tournament, TournamentError}, trace::Tracer, validation::validate, events::diff, timing::{TimingStats, Watchdog}};
+
+/// Some utility functions to calculate some things
+mod utils;
+/// Functionality used to connect to the server
+mod network;
+/// Ant controll
+mod ai;
+/// Command line argument parsing
+mod cli;
+/// Summary of a finished game
+mod summary;
+/// Recording of games into files
+mod recording;
+/// Replay of recorded games through the AI
+mod replay;
+/// Conversion of recordings from and to JSON lines
+mod json_lines;
+/// Recording of games played by other clients
+mod proxy;
+/// Annotated hex dumps of the data exchanged with the server
+mod trace;
+/// Consistency checks of received turns
+mod validation;
+/// Events that happened between two turns
+mod events;
+/// Timing of turns and the AI deadline
+mod timing;
+/// Rules of the game
+mod game;
+/// Game server that is compatible with the AntServer protocol
+mod server;
+/// Games that run without sockets
+mod simulator;
+/// Situations of a game written down by hand
+mod scenario;
+/// Games between variants of the client and their ratings
+mod tournament;
+
+pub use server::run_server;
+pub use simulator::{Simulator, SimulatorError};
+pub use scenario::{Scenario, ScenarioError};
+pub use cli::Args;
+
+//pub const TEAM_NAME: &str = "Rust_pirates";
+//const SERVER_ADDRESS: &str = "127.0.0.1:5000";
+
+/// All coordinates of the home bases, coordinates for base 0 are in index 0.
+const HOME_BASE_COORDINATES: [(u16, u16); 16] = [(100, 100), (300, 100), (500, 100),
+    (700, 100), (900, 100), (900, 300), (900, 500), (900, 700), (900, 900), (700, 900),
+    (500, 900), (300, 900), (100, 900), (100, 700), (100, 500), (100, 300)];
+/// Points where ants will navigate to to lead them to their homebase  without clashing into an enemy homebase.
+const HOME_BASE_BEACONS: [(u16, u16); 16] = [(110, 110), (300, 110), (500, 110), 
+    (700, 110), (890, 110), (890, 300), (890, 500), (890, 700), (890, 890), (700, 890),
+    (500, 890), (300, 890), (100, 890), (110, 700), (110, 500), (110, 300)];
+
+/// Exit code used when the game ended normally or a tool finished successfully
+const EXIT_SUCCESS: i32 = 0;
+/// Exit code used when the arguments are not valid
+const EXIT_INVALID_ARGS: i32 = 1;
+/// Exit code used when the connection to the server failed or was closed before the game started
+const EXIT_CONNECTION_FAILED: i32 = 2;
+/// Exit code used when the data received from the server could not be read
+const EXIT_PROTOCOL_ERROR: i32 = 3;
+
+/// Runs the client with the command line arguments, this is the `main` of the `tondorf_ants` binary.
+pub fn run_client() {
+    println!("Hello from tondorf_ants! \\O/");
+    let args = Args::parse();
+    if args.ant_help {
+        print_ant_help();
+        exit(0);
+    }
+    if let Some(command) = &args.command {
+        exit(run_command(command, &args));
+    }
+    // Spectators don't control any ants
+    let seed = seed_or_random(args.seed);
+    let ant_jobs = if args.spectate { Vec::new() } else { set_ant_jobs(&args, &mut StdRng::seed_from_u64(seed)) };
+    let mut rng = ai_rng(seed);
+    let register = match Register::new(&args) {
+        Ok(register) => register,
+        Err(e) => {
+            println!("Unable to start client: Invalid team name: {}", e);
+            exit(EXIT_INVALID_ARGS);
+        }
+    };
+    let reconnect_policy = ReconnectPolicy::from_args(&args);
+    let mut state = GameState::default();
+    if let Some(path) = &args.record {
+        // With a watchdog the random decisions depend on the turns the AI thread got to in time, a replay can not repeat them
+        let header = RecordingHeader::new(register.team_name().clone(), &ant_jobs, args.ai_budget.is_none().then_some(seed));
+        match Recorder::create(path, &header) {
+            Ok(recorder) => state.recorder = Some(recorder),
+            Err(e) => {
+                println!("Unable to start client: Unable to create recording {}: {}", path.display(), e);
+                exit(EXIT_INVALID_ARGS);
+            }
+        }
+    }
+    if let Some(path) = &args.trace {
+        match Tracer::create(path) {
+            Ok(tracer) => state.tracer = Some(tracer),
+            Err(e) => {
+                println!("Unable to start client: Unable to create trace {}: {}", path.display(), e);
+                exit(EXIT_INVALID_ARGS);
+            }
+        }
+    }
+    if let Some(budget) = args.ai_budget {
+        state.watchdog = Some(Watchdog::spawn(Duration::from_millis(budget), args.clone(), ant_jobs.clone(), rng.clone()));
+    }
+    exit(play_with_reconnect(&args, &register, &ant_jobs, &mut state, &mut rng, reconnect_policy.as_ref()));
+}
+
+/// Plays the game and connects again as allowed by the reconnect policy when the connection is lost.
+///
+/// The game is over when the server closes a connection on which at least one frame was received.
+/// Returns the code the client should exit with.
+fn play_with_reconnect(args: &Args, register: &Register, ant_jobs: &[AntJob], state: &mut GameState, rng: &mut StdRng,
+    reconnect_policy: Option<&ReconnectPolicy>) -> i32 {
+    let mut attempt = 0;
+    loop {
+        let frames_before = state.frames_received;
+        let result = match connect(args) {
+            Ok(tcp_stream) => {
+                println!("Connection established!");
+                play(tcp_stream, register, args, ant_jobs, state, rng)
+            }
+            Err(e) => Err(ProtocolError::Io(e)),
+        };
+        // A connection only counts as successful when the server sent frames on it
+        let played = state.frames_received > frames_before;
+        if played {
+            attempt = 0;
+        }
+        let e = match result {
+            Ok(()) if played || reconnect_policy.is_none() => {
+                state.finish_recording();
+                return state.game_over(args.spectate);
+            }
+            Ok(()) => ProtocolError::Io(io::Error::new(io::ErrorKind::ConnectionAborted, "server closed the connection before sending a turn")),
+            Err(e) => e,
+        };
+        // Invalid turns are sent again after reconnecting, so only a lost connection is retried
+        let retry = !matches!(e, ProtocolError::Malformed { .. } | ProtocolError::OutOfRange { .. });
+        match reconnect_policy.filter(|_| retry).and_then(|policy| policy.delay(attempt)) {
+            Some(delay) => {
+                attempt += 1;
+                println!("Connection to server failed: {}", e);
+                println!("Reconnecting in {:.1}s (attempt {})", delay.as_secs_f64(), attempt);
+                sleep(delay);
+            }
+            None => {
+                println!("Error: {}", e);
+                state.finish_recording();
+                return match e {
+                    ProtocolError::UnexpectedEof | ProtocolError::Malformed { .. } | ProtocolError::OutOfRange { .. } => EXIT_PROTOCOL_ERROR,
+                    _ => EXIT_CONNECTION_FAILED,
+                };
+            }
+        }
+    }
+}
+
+/// Runs the tool selected by the subcommand.
+/// 
+/// Returns the code the client should exit with.
+fn run_command(command: &Command, args: &Args) -> i32 {
+    match command {
+        Command::Replay { recording, output } => {
+            let mut reader = match RecordingReader::open(recording) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    println!("Unable to read recording {}: {}", recording.display(), e);
+                    return EXIT_PROTOCOL_ERROR;
+                }
+            };
+            let seed = seed_or_random(args.seed.or(reader.header().seed));
+            let ant_jobs = if args.default_jobs || args.random_jobs || args.gatherer_ants.is_some()
+                || args.offensive_ants.is_some() || args.waste_mover_ants.is_some() {
+                set_ant_jobs(args, &mut StdRng::seed_from_u64(seed))
+            } else {
+                match reader.header().jobs.iter().copied().collect::<Option<Vec<AntJob>>>() {
+                    Some(jobs) => jobs,
+                    None => {
+                        println!("The recording does not contain the ant jobs, set them with -g, -o and -w, -d or -r");
+                        return EXIT_INVALID_ARGS;
+                    }
+                }
+            };
+            let mut sink: Box<dyn Write> = match output {
+                Some(path) => match File::create(path) {
+                    Ok(file) => Box::new(BufWriter::new(file)),
+                    Err(e) => {
+                        println!("Unable to create {}: {}", path.display(), e);
+                        return EXIT_INVALID_ARGS;
+                    }
+                },
+                None => Box::new(io::sink()),
+            };
+            match replay(&mut reader, &mut sink, args, &ant_jobs, &mut ai_rng(seed)) {
+                Ok(stats) => {
+                    println!("{}", stats);
+                    EXIT_SUCCESS
+                }
+                Err(e) => {
+                    println!("Unable to read recording {}: {}", recording.display(), e);
+                    EXIT_PROTOCOL_ERROR
+                }
+            }
+        }
+        Command::Export { recording, output, from, to } => {
+            let result = RecordingReader::open(recording).and_then(|mut reader| {
+                let mut output = BufWriter::new(File::create(output)?);
+                let written = export(&mut reader, &mut output, *from, *to)?;
+                output.flush()?;
+                Ok(written)
+            });
+            match result {
+                Ok(written) => {
+                    println!("Exported {} turns to {}", written, output.display());
+                    EXIT_SUCCESS
+                }
+                Err(e) => {
+                    println!("Unable to export {}: {}", recording.display(), e);
+                    EXIT_PROTOCOL_ERROR
+                }
+            }
+        }
+        Command::Proxy { listen, recording } => {
+            let listener = match TcpListener::bind(listen) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    println!("Unable to listen on {}: {}", listen, e);
+                    return EXIT_CONNECTION_FAILED;
+                }
+            };
+            println!("Waiting for client on {}", listen);
+            let result = listener.accept().map_err(ProtocolError::from).and_then(|(client, address)| {
+                println!("Client connected from {}", address);
+                proxy(client, args, BufWriter::new(File::create(recording)?))
+            });
+            match result {
+                Ok(stats) => {
+                    println!("{}", stats);
+                    EXIT_SUCCESS
+                }
+                Err(e) => {
+                    println!("Error: {}", e);
+                    EXIT_CONNECTION_FAILED
+                }
+            }
+        }
+        Command::Tournament { variants, games, turns, players, results } => {
+            let variants = match fs::read_to_string(variants).map_err(TournamentError::from).and_then(|text| parse_variants(&text)) {
+                Ok(variants) => variants,
+                Err(e) => {
+                    println!("Unable to read variants {}: {}", variants.display(), e);
+                    return EXIT_INVALID_ARGS;
+                }
+            };
+            let mut sink: Box<dyn Write> = match results {
+                Some(path) => match File::create(path) {
+                    Ok(file) => Box::new(BufWriter::new(file)),
+                    Err(e) => {
+                        println!("Unable to create {}: {}", path.display(), e);
+                        return EXIT_INVALID_ARGS;
+                    }
+                },
+                None => Box::new(io::sink()),
+            };
+            let seed = seed_or_random(args.seed);
+            match tournament(&variants, *games, *turns, usize::from(*players), seed, &mut sink).and_then(|standings| {
+                sink.flush()?;
+                Ok(standings)
+            }) {
+                Ok(standings) => {
+                    println!("{}", standings);
+                    EXIT_SUCCESS
+                }
+                Err(e) => {
+                    println!("Error: {}", e);
+                    EXIT_INVALID_ARGS
+                }
+            }
+        }
+        Command::Import { input, output } => {
+            let result = File::open(input).map_err(ProtocolError::from).and_then(|file| {
+                import(BufReader::new(file), BufWriter::new(File::create(output)?))
+            });
+            match result {
+                Ok(written) => {
+                    println!("Imported {} turns into {}", written, output.display());
+                    EXIT_SUCCESS
+                }
+                Err(e) => {
+                    println!("Unable to import {}: {}", input.display(), e);
+                    EXIT_PROTOCOL_ERROR
+                }
+            }
+        }
+    }
+}
+
+/// State of the game that is kept when the connection is lost and established again.
+#[derive(Debug, Default)]
+struct GameState {
+    /// The last turn that was received
+    last_turn: Option<Turn>,
+    /// Number of turns that were played
+    turns_played: u64,
+    /// Number of frames that were received over all connections
+    frames_received: u64,
+    /// Records the game when `--record` is set
+    recorder: Option<Recorder<BufWriter<File>>>,
+    /// Traces the exchanged data when `--trace` is set
+    tracer: Option<Tracer<BufWriter<File>>>,
+    /// Time between turns and time needed to answer them
+    timing: TimingStats,
+    /// Limits the time the AI may take when `--ai-budget` is set
+    watchdog: Option<Watchdog>,
+}
+
+impl GameState {
+
+    /// Writes the index of the recording, if the game is recorded.
+    fn finish_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(e) = recorder.finish() {
+                println!("Unable to finish recording: {}", e);
+            }
+        }
+    }
+
+    /// Writes to the trace, if the exchanged data is traced.
+    /// 
+    /// Tracing is stopped when writing fails.
+    fn trace<F: FnOnce(&mut Tracer<BufWriter<File>>) -> io::Result<()>>(&mut self, write: F) {
+        if let Some(tracer) = &mut self.tracer {
+            if let Err(e) = write(tracer) {
+                println!("Unable to write trace, tracing stopped: {}", e);
+                self.tracer = None;
+            }
+        }
+    }
+
+    /// Prints the summary of the game and returns the code the client should exit with.
+    /// 
+    /// Spectators get the final scoreboard instead of the summary of the own team.
+    fn game_over(&self, spectate: bool) -> i32 {
+        match &self.last_turn {
+            Some(last_turn) if spectate => {
+                println!("Game over after {} turns!", self.turns_played);
+                println!("{}", Scoreboard::new(last_turn));
+                println!("{}", self.timing);
+                EXIT_SUCCESS
+            }
+            Some(last_turn) => {
+                println!("{}", GameSummary::new(last_turn, self.turns_played));
+                println!("{}", self.timing);
+                EXIT_SUCCESS
+            }
+            None => {
+                println!("Server closed the connection before the game started");
+                EXIT_CONNECTION_FAILED
+            }
+        }
+    }
+}
+
+/// Registers at the server and plays the game on the connection.
+/// 
+/// Returns `Ok` when the server closed the connection between two turns, which ends the game when it sent turns before.
+/// Returns an error when the connection was lost.
+fn play(mut tcp_stream: TcpStream, register: &Register, args: &Args, ant_jobs: &[AntJob], state: &mut GameState, rng: &mut StdRng) -> Result<(), ProtocolError> {
+    tcp_stream.write_all(&register.as_bytes())?;
+    state.trace(|tracer| tracer.register(&register.as_bytes()));
+    let mut next_frames: Box<dyn FnMut() -> Result<Vec<Vec<u8>>, ProtocolError>> = if args.catch_up {
+        let mut reader = FrameReader::spawn(tcp_stream.try_clone()?)?;
+        Box::new(move || reader.next_frames())
+    } else {
+        let mut br = BufReader::new(tcp_stream.try_clone()?);
+        Box::new(move || Ok(read_frame(&mut br)?.into_iter().collect()))
+    };
+    let mut last_frame: Option<Instant> = None;
+    loop {
+        let mut frames = next_frames()?;
+        state.frames_received += frames.len() as u64;
+        let frame = match frames.pop() {
+            Some(frame) => frame,
+            // The server closed the connection between two turns
+            None => return Ok(()),
+        };
+        // Older frames are only traced and recorded, there is no time to answer them
+        for skipped in frames {
+            let timestamp = timestamp_now();
+            state.trace(|tracer| tracer.frame(timestamp, &skipped));
+            if let Some(recorder) = &mut state.recorder {
+                if let Err(e) = recorder.record(&RecordedTurn { timestamp, frame: skipped, actions: NO_ACTIONS }) {
+                    println!("Unable to record turn, recording stopped: {}", e);
+                    state.recorder = None;
+                }
+            }
+            state.timing.skipped_frames += 1;
+        }
+        let received = Instant::now();
+        if let Some(last_frame) = last_frame.replace(received) {
+            state.timing.intervals.add(received - last_frame);
+        }
+        let timestamp = timestamp_now();
+        state.trace(|tracer| tracer.frame(timestamp, &frame));
+        let t = match Turn::from_bytes(&frame) {
+            Ok(t) => t,
+            // The whole frame has been read, the next one can still be decoded
+            Err(e @ (ProtocolError::OutOfRange { .. } | ProtocolError::Malformed { .. })) => {
+                println!("Skipping turn: {}", e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let violations = validate(&t);
+        if args.strict && !violations.is_empty() {
+            let reasons = violations.iter().map(ToString::to_string).collect::<Vec<String>>();
+            return Err(ProtocolError::Malformed { field: "turn", reason: reasons.join(", ") });
+        }
+        for violation in violations {
+            println!("Warning: {}", violation);
+        }
+        if args.print_events {
+            if let Some(last_turn) = &state.last_turn {
+                for event in diff(last_turn, &t) {
+                    println!("Event: {}", event);
+                }
+            }
+        }
+        let actions = if args.spectate {
+            let points_changed = match &state.last_turn {
+                Some(last_turn) => last_turn.teams.iter().zip(&t.teams).any(|(old, new)| old.points != new.points),
+                None => true,
+            };
+            if points_changed {
+                println!("{}", Scoreboard::new(&t));
+            }
+            NO_ACTIONS
+        } else {
+            let actions = match &mut state.watchdog {
+                Some(watchdog) => {
+                    let (actions, overrun) = watchdog.actions(t.clone());
+                    if overrun {
+                        println!("Warning: AI did not answer within its budget, sending fallback actions");
+                        state.timing.overruns += 1;
+                    }
+                    if let Err(e) = tcp_stream.write_all(&actions) {
+                        println!("Error, unable to send action: {}", e);
+                    }
+                    actions
+                }
+                None => turn(&mut tcp_stream, &t, args, ant_jobs, rng),
+            };
+            state.timing.compute.add(received.elapsed());
+            state.trace(|tracer| tracer.actions(&actions));
+            actions
+        };
+        if let Some(recorder) = &mut state.recorder {
+            if let Err(e) = recorder.record(&RecordedTurn { timestamp, frame, actions }) {
+                println!("Unable to record turn, recording stopped: {}", e);
+                state.recorder = None;
+            }
+        }
+        state.turns_played += 1;
+        state.last_turn = Some(t);
+    }
+}
+
+/// Different types of ants
+#[derive(Debug, Ord, PartialEq, PartialOrd, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum AntJob {
+    /// These ants will focus on gathering sugar back to the base
+    Gatherer,
+    /// These ants will seek to attack enemy ants, prioritiesed as followed: toxin > sugar > none.
+    Offensive,
+    /// These ants will bring toxic waste into the enemy base that is currently leading the game.
+    /// If no more toxic waste is found they will performe the Offensive ants job.
+    WasteMover,
+}
+
+/// Prints help about the different ant types and what the priority of actions is.
+fn print_ant_help() {
+    let mut s = String::from("All ants have the following priorities:\n");
+    s.push_str(" 1. If health is <= 3 move to base\n");
+    s.push_str(" 2. if toxins are carried deliver them to base of enemy with most points\n");
+    s.push_str(" 3. Job specific tasks\n\n");
+    s.push_str("Gatherer ants:\n");
+    s.push_str(" 1. Move home when sugar is carried\n");
+    s.push_str(" 2. Walk thowards nearest piece of sugar\n");
+    s.push_str(" 3. If there is no nearest sugar remain at current position\n\n");
+    s.push_str("Offensive ants:\n");
+    s.push_str(" 1. Attack nearest enemy ant with at most 'max_health' amount of health\n");
+    s.push_str(" 2. Gatherer ants tasks\n\n");
+    s.push_str("Waste mover ants:\n");
+    s.push_str(" 1. Walk thowards nearest toxic waste\n");
+    s.push_str(" 2. Offensive ants tasks\n");
+    println!("{}", s);
+}
+
+/// Returns the seed from the arguments or a random seed when none was set.
+/// 
+/// The seed is printed, so that a game can be played again with the same random decisions.
+/// Job selection and the AI use separate generators that are seeded with it, so a replay makes the same decisions
+/// regardless of whether the jobs are taken from the recording or selected again.
+pub(crate) fn seed_or_random(seed: Option<u64>) -> u64 {
+    let seed = seed.unwrap_or_else(|| thread_rng().gen());
+    println!("Using seed {}", seed);
+    seed
+}
+
+/// Mixed into the seed of the AI generator, so that its draws are independent of the job selection
+const AI_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Returns the generator for the decisions of the AI.
+///
+/// The job selection uses a generator seeded with `seed` itself.
+pub(crate) fn ai_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ AI_SEED_SALT)
+}
+
+/// Sets the jobs for the ants by analyzing the input parameters.
+/// 
+/// Random jobs are drawn from `rng`.
+/// 
+/// Returns a vector with exactly 16 jobs, job at index 0 is for ant 0 and so forth.
+/// 
+/// Terminates the program when args are not valid.
+fn set_ant_jobs<R: Rng>(args: &Args, rng: &mut R) -> Vec<AntJob>  {
+    let mut jobs = Vec::new();
+    if args.default_jobs {
+        // Use default ant settings
+        for _i in 0..7 {
+            jobs.push(AntJob::Gatherer);
+        }
+        for _i in 0..7 {
+            jobs.push(AntJob::Offensive);
+        }
+        for _i in 0..2 {
+            jobs.push(AntJob::WasteMover);
+        }
+    } else if args.random_jobs {
+        // Select random jobs for ants
+        for _i in 0..16 {
+            let random = rng.gen_range(0..3);
+            match random {
+                0 => jobs.push(AntJob::Gatherer),
+                1 => jobs.push(AntJob::Offensive),
+                2 => jobs.push(AntJob::WasteMover),
+                _ => panic!("Unable to select random job, iillegal number: {}", random),
+            }
+        }
+    } else {
+        // Use user ant settings
+        for _i in  0..args.gatherer_ants.unwrap_or(0) {
+            jobs.push(AntJob::Gatherer);
+        }
+        for _i in 0..args.offensive_ants.unwrap_or(0) {
+            jobs.push(AntJob::Offensive);
+        }
+        for _i in 0..args.waste_mover_ants.unwrap_or(0) {
+            jobs.push(AntJob::WasteMover);
+        }
+    }
+    if jobs.len() != 16 {
+        println!("Unable to start client: Total job count != 16, was {}", jobs.len());
+        exit(EXIT_INVALID_ARGS);
+    }
+    jobs
+}
+
+#[derive(Debug, Ord, PartialEq, PartialOrd, Eq)]
+enum AntCargo {
+    Sugar,
+    ToxicWaste,
+}
+
+#[derive(Debug, Eq)]
+pub struct Ant {
+    /// Id of this ant
+    id: u8,
+    /// Current position on the board
+    pos: (u16, u16),
+    /// Current health
+    health: u8,
+    /// Stores what the ant is carrying
+    cargo: Option<AntCargo>,
+    /// The job this ant is directed to do
+    job: Option<AntJob>,
+}
+
+impl Ant {
+    /// Creates a new ant
+    fn new(id: u8, pos: (u16, u16), health: u8, cargo: Option<AntCargo>, job: Option<AntJob>) -> Self {
+        Self {
+            id,
+            pos,
+            health,
+            cargo,
+            job,
+        }
+    }
+
+    /// Returns the direction in which the ant should go to reach target.
+    fn move_direction(&self, target: (u16, u16)) -> u8 {
+        if self.pos.0 > target.0 && self.pos.1 > target.1 {
+            return 1;
+        }
+        if self.pos.0 < target.0 && self.pos.1 > target.1 {
+            return 3;
+        }
+        if self.pos.0 < target.0 && self.pos.1 < target.1 {
+            return 9;
+        }
+        if self.pos.0 > target.0 && self.pos.1 < target.1 {
+            return 7;
+        }
+        if self.pos.0 == target.0 && self.pos.1 < target.1 {
+            return 8;
+        }
+        if self.pos.0 == target.0 && self.pos.1 > target.1 {
+            return 2;
+        }
+        if self.pos.0 < target.0 && self.pos.1 == target.1 {
+            return 6;
+        }
+        if self.pos.0 > target.0 && self.pos.1 == target.1 {
+            return 4;
+        }
+        5
+    }
+}
+
+impl PartialOrd for Ant {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ant {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl PartialEq for Ant {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Position for Ant {
+    fn pos(&self) -> (u16, u16) {
+        self.pos
+    }
+}
+
+struct Ants {
+    ants: Vec<Ant>,
+    /// Stores all positions the ants are at the moment.
+    /// Used to determine possible collisions when ants are moving.
+    ant_positions: Vec<(u16, u16)>,
+}
+
+impl Ants {
+    /// Creates ants from the turn.
+    /// 
+    /// `team_id` - determines for which team the ants should be build. If `None` ants will be build for own team.
+    fn from_turn(turn: &Turn, team_id: Option<i16>, ant_jobs: &[AntJob]) -> Self {
+        let team_id = match team_id {
+            None => turn.team_id,
+            Some(id) => id,
+        };
+        let mut ants = Vec::new();
+        let mut ant_positions = Vec::new();
+        let mut missing_ants:HashSet<u8> = (0..16).collect(); // Stores ids of ants that are not yet added to the ants vec
+        for object in &turn.objects {
+            // Check object team id
+            if i16::from(object.b1.lower) != team_id {
+                continue;
+            }
+            // Check if object is ant
+            let kind = object.kind();
+            if !kind.is_ant() {
+                continue;
+            }
+            if team_id == turn.team_id {
+                ants.push(Ant::new(object.b2.upper, object.pos,object.b2.lower, kind.cargo(), Some(ant_jobs[object.b2.upper as usize])));
+            } else {
+                ants.push(Ant::new(object.b2.upper, object.pos,object.b2.lower, kind.cargo(), None));
+            }
+            ant_positions.push(object.pos);
+            missing_ants.remove(&object.b2.upper);
+        }
+        // Add dead ants to vec
+        // This is done to make sure that an action for each ant is submitted to the server even when ants are dead
+        for id in &missing_ants {
+            ants.push(Ant::new(*id, (0, 0), 0, None, None));
+        }
+        ants.sort();
+        Self {
+            ants,
+            ant_positions,
+        }
+    }
+
+    /// Prints the ants to the console
+    fn print_ants(&self) {
+        println!("Ants: ");
+        for ant in &self.ants {
+            println!("{:?}", ant);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    /// Team id of client
+    team_id: i16,
+    teams: Vec<Team>,// 16 Teams are required
+    _nr_of_objects: u16,
+    objects: Vec<Object>,
+}
+
+impl Turn {
+
+    /// Returns a vector that contains all positions of objects. This includes ants.
+    fn object_positions(&self) -> Vec<(u16, u16)> {
+        let mut positions = Vec::new();
+        for object in &self.objects {
+            positions.push(object.pos);
+        }
+        positions
+    }
+
+    /// Builds ants for all enemy teams.
+    /// Only includes ants that are alive.
+    /// 
+    /// - `live_threshold` can be set to limit the ants that are shown to only ants with less or equal amount of health.
+    fn enemy_ants(&self, live_threshold: Option<u8>, ant_jobs: &[AntJob]) -> Vec<Ant> {
+        let mut ants = Vec::new();
+        for i in 0..15 {
+            if i == self.team_id {
+                continue;
+            }
+            for ant in Ants::from_turn(self, Some(i), ant_jobs).ants {
+                if ant.health == 0 {
+                    continue;
+                }
+                if live_threshold.is_some() && ant.health > live_threshold.unwrap() {
+                    continue;
+                }
+                ants.push(ant);
+            }
+        }
+        ants
+    }
+
+}
+
+#[cfg(test)]
+impl Turn {
+
+    /// Creates a turn for tests with 16 teams that are named `ants` and have 0 points and 16 remaining ants.
+    /// 
+    /// The number of objects is taken from `objects`.
+    pub(crate) fn test(team_id: i16, objects: Vec<Object>) -> Self {
+        Self {
+            team_id,
+            teams: (0..16).map(|id| Team { id, points: 0, remaining_ants: 16, team_name: TeamName::new("ants").unwrap() }).collect(),
+            _nr_of_objects: objects.len() as u16,
+            objects,
+        }
+    }
+
+    /// Changes every team of the turn with `change`.
+    pub(crate) fn with_teams<F: FnMut(&mut Team)>(mut self, change: F) -> Self {
+        self.teams.iter_mut().for_each(change);
+        self
+    }
+
+    /// Adds an object and keeps the number of objects in sync.
+    pub(crate) fn push_object(&mut self, object: Object) {
+        self.objects.push(object);
+        self._nr_of_objects = self.objects.len() as u16;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Team {
+    id: i16,
+    points: u16,
+    remaining_ants: u16,
+    team_name: TeamName,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Object {
+    b1: Pair,// Contains object type and team id
+    b2: Pair,// Contains ant ID and ant health
+    pos: (u16, u16),
+}
+
+impl Object {
+
+    /// Returns the type of this object
+    fn kind(&self) -> ObjectKind {
+        ObjectKind::from_nibble(self.b1.upper)
+    }
+}
+
+/// The type of an object as stored in the upper 4 bits of its first byte.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ObjectKind {
+    /// An ant that does not carry anything
+    Ant,
+    /// A piece of sugar lying on the board
+    Sugar,
+    /// An ant carrying a piece of sugar
+    AntWithSugar,
+    /// Toxic waste lying on the board
+    ToxicWaste,
+    /// An ant carrying toxic waste
+    AntWithToxicWaste,
+    /// A value that is not defined by the protocol
+    Unknown(u8),
+}
+
+impl ObjectKind {
+
+    /// Decodes the object type from the 4 bit value.
+    fn from_nibble(nibble: u8) -> Self {
+        match nibble {
+            1 => ObjectKind::Ant,
+            2 => ObjectKind::Sugar,
+            3 => ObjectKind::AntWithSugar,
+            4 => ObjectKind::ToxicWaste,
+            5 => ObjectKind::AntWithToxicWaste,
+            _ => ObjectKind::Unknown(nibble),
+        }
+    }
+
+    /// Returns the type of an ant that carries `cargo`.
+    fn ant(cargo: Option<&AntCargo>) -> Self {
+        match cargo {
+            None => ObjectKind::Ant,
+            Some(AntCargo::Sugar) => ObjectKind::AntWithSugar,
+            Some(AntCargo::ToxicWaste) => ObjectKind::AntWithToxicWaste,
+        }
+    }
+
+    /// Encodes the object type into the 4 bit value, the reverse of `from_nibble`.
+    fn to_nibble(self) -> u8 {
+        match self {
+            ObjectKind::Ant => 1,
+            ObjectKind::Sugar => 2,
+            ObjectKind::AntWithSugar => 3,
+            ObjectKind::ToxicWaste => 4,
+            ObjectKind::AntWithToxicWaste => 5,
+            ObjectKind::Unknown(nibble) => nibble,
+        }
+    }
+
+    /// Returns true if the object is an ant
+    fn is_ant(&self) -> bool {
+        matches!(self, ObjectKind::Ant | ObjectKind::AntWithSugar | ObjectKind::AntWithToxicWaste)
+    }
+
+    /// Returns the cargo an ant of this type is carrying or `None` if no cargo is carried.
+    fn cargo(&self) -> Option<AntCargo> {
+        match self {
+            ObjectKind::AntWithSugar => Some(AntCargo::Sugar),
+            ObjectKind::AntWithToxicWaste => Some(AntCargo::ToxicWaste),
+            _ => None,
+        }
+    }
+}
+
+impl Position for Object {
+    fn pos(&self) -> (u16, u16) {
+        self.pos
+    }
+}
+
+impl Position for &Object {
+    fn pos(&self) -> (u16, u16) {
+        self.pos
+    }
+}
+
+/// Represents a data type that uses an u8 to store two 4 bit values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pair {
+    upper: u8,
+    lower: u8,
+}
+
+/// Trait to get position of objects
+pub trait Position {
+    /// Returns the position
+    fn pos(&self) -> (u16, u16);
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rand::{distributions::Standard, rngs::StdRng, Rng, SeedableRng};
+
+    use std::{io::{Read, Write}, net::TcpListener, sync::{Arc, atomic::{AtomicU32, Ordering}}, thread};
+
+    use crate::{Ant, Turn, ObjectKind, AntCargo, GameState, EXIT_CONNECTION_FAILED, EXIT_SUCCESS, set_ant_jobs, ai_rng, play_with_reconnect,
+        cli::Args, network::{Register, ReconnectPolicy}};
+
+    /// Starts a server that reads the registration of every client, sends it `frames` frames and closes the connection.
+    ///
+    /// Returns the address of the server and the number of accepted connections.
+    fn closing_server(frames: usize) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicU32::new(0));
+        let counter = accepted.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                stream.read_exact(&mut [0; 18]).unwrap();
+                for _ in 0..frames {
+                    stream.write_all(&Turn::test(0, Vec::new()).to_bytes()).unwrap();
+                    stream.read_exact(&mut [0; 16]).unwrap();
+                }
+            }
+        });
+        (address, accepted)
+    }
+
+    /// Plays with up to two reconnects against the server, returns the exit code.
+    fn play_against(address: &str) -> i32 {
+        let args = Args::parse_from(["tondorf_ants", "-d", "--server", address, "--reconnect", "2", "--reconnect-delay", "1"]);
+        let ant_jobs = set_ant_jobs(&args, &mut ai_rng(0));
+        let policy = ReconnectPolicy::from_args(&args);
+        play_with_reconnect(&args, &Register::new(&args).unwrap(), &ant_jobs, &mut GameState::default(), &mut ai_rng(0), policy.as_ref())
+    }
+
+    #[test]
+    fn test_ant_movement() {
+        let ant = Ant::new(0, (1, 1), 10, None, None);
+        assert_eq!(ant.move_direction((0,0)), 1);
+        assert_eq!(ant.move_direction((1,0)), 2);
+        assert_eq!(ant.move_direction((2,0)), 3);
+        assert_eq!(ant.move_direction((0,1)), 4);
+        assert_eq!(ant.move_direction((1,1)), 5);
+        assert_eq!(ant.move_direction((2,1)), 6);
+        assert_eq!(ant.move_direction((0,2)), 7);
+        assert_eq!(ant.move_direction((1,2)), 8);
+        assert_eq!(ant.move_direction((2,2)), 9);
+    }
+
+    #[test]
+    fn test_object_kind() {
+        assert_eq!(ObjectKind::from_nibble(1), ObjectKind::Ant);
+        assert_eq!(ObjectKind::from_nibble(2), ObjectKind::Sugar);
+        assert_eq!(ObjectKind::from_nibble(3), ObjectKind::AntWithSugar);
+        assert_eq!(ObjectKind::from_nibble(4), ObjectKind::ToxicWaste);
+        assert_eq!(ObjectKind::from_nibble(5), ObjectKind::AntWithToxicWaste);
+        assert_eq!(ObjectKind::from_nibble(0), ObjectKind::Unknown(0));
+        assert_eq!(ObjectKind::from_nibble(7), ObjectKind::Unknown(7));
+        assert!(!ObjectKind::Sugar.is_ant());
+        assert!(!ObjectKind::Unknown(7).is_ant());
+        assert_eq!(ObjectKind::Sugar.cargo(), None);
+        assert_eq!(ObjectKind::AntWithToxicWaste.cargo(), Some(AntCargo::ToxicWaste));
+        for nibble in 0..16 {
+            assert_eq!(ObjectKind::from_nibble(nibble).to_nibble(), nibble);
+        }
+        assert_eq!(ObjectKind::ant(None), ObjectKind::Ant);
+        assert_eq!(ObjectKind::ant(Some(&AntCargo::Sugar)), ObjectKind::AntWithSugar);
+        assert_eq!(ObjectKind::ant(Some(&AntCargo::ToxicWaste)).cargo(), Some(AntCargo::ToxicWaste));
+    }
+
+    #[test]
+    fn test_reconnect() {
+        // A server that closes the connection before the first turn does not reset the attempts
+        let (address, accepted) = closing_server(0);
+        assert_eq!(play_against(&address), EXIT_CONNECTION_FAILED);
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+
+        // The game is over when the server closes a connection after sending turns
+        let (address, accepted) = closing_server(2);
+        assert_eq!(play_against(&address), EXIT_SUCCESS);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_random_jobs_seed() {
+        let args = Args::parse_from(["tondorf_ants", "-r", "--seed", "42"]);
+        let jobs = set_ant_jobs(&args, &mut StdRng::seed_from_u64(42));
+        assert_eq!(jobs.len(), 16);
+        assert_eq!(set_ant_jobs(&args, &mut StdRng::seed_from_u64(42)), jobs);
+        // The AI does not repeat the draws of the job selection
+        let job_draws: Vec<u32> = StdRng::seed_from_u64(42).sample_iter(Standard).take(4).collect();
+        let ai_draws: Vec<u32> = ai_rng(42).sample_iter(Standard).take(4).collect();
+        assert_ne!(job_draws, ai_draws);
+        assert_eq!(ai_rng(42).sample_iter(Standard).take(4).collect::<Vec<u32>>(), ai_draws);
+    }
+}
@@ -1,96 +1,505 @@
-use std::{io::{Bytes, BufReader}, net::TcpStream};
+use std::{io::{self, BufReader, Read, Write}, fmt::{self, Display}, error::Error, time::Duration, net::{TcpStream, IpAddr, SocketAddr, ToSocketAddrs, Shutdown}, sync::mpsc::{self, Receiver}, thread};
 
-use crate::{Turn, utils::{read_to_two_byte_array, bytes_to_string}, Team, Object, Pair, cli::Args};
+use rand::{thread_rng, Rng};
+
+use crate::{Turn, utils::le_u16, Team, Object, Pair, cli::Args};
 
 const CLIENT_TYPE: u16 = 1;
+/// Client type of an observer that does not control any ants.
+///
+/// The AntServer protocol only defines the client type 1 for players, 0 is an assumption that `tondorf_server` follows.
+/// Other servers may reject it.
+const SPECTATOR_CLIENT_TYPE: u16 = 0;
+/// Number of bytes used to describe a single team.
+const TEAM_LEN: usize = 20;
+/// Number of bytes used to describe a single object.
+const OBJECT_LEN: usize = 6;
+/// Number of bytes before the first object: team id, 16 teams and the number of objects.
+const HEADER_LEN: usize = 2 + 16 * TEAM_LEN + 2;
+
+/// Errors that can occur while decoding data received from the server.
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// The input ended before the frame was complete.
+    UnexpectedEof,
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+    /// A field could not be decoded.
+    Malformed { field: &'static str, reason: String },
+    /// A field was decoded but its value is outside of the allowed range.
+    OutOfRange { field: &'static str, value: i64 },
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::UnexpectedEof => write!(f, "unexpected end of input"),
+            ProtocolError::Io(e) => write!(f, "i/o error: {}", e),
+            ProtocolError::Malformed { field, reason } => write!(f, "malformed {}: {}", field, reason),
+            ProtocolError::OutOfRange { field, value } => write!(f, "{} out of range: {}", field, value),
+        }
+    }
+}
+
+impl Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProtocolError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ProtocolError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            return ProtocolError::UnexpectedEof;
+        }
+        ProtocolError::Io(e)
+    }
+}
+
+/// Resolves the address of the server from `--server` or `--ip` and `--port`.
+/// 
+/// When `--server` does not contain a port the port from `--port` is used.
+/// IP addresses are used as they are, IPv6 addresses with or without brackets, only host names are looked up.
+pub fn server_addresses(args: &Args) -> io::Result<Vec<SocketAddr>> {
+    let addresses = match &args.server {
+        Some(server) => {
+            if let Ok(address) = server.parse::<SocketAddr>() {
+                return Ok(vec![address]);
+            }
+            if let Ok(ip) = server.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+                return Ok(vec![SocketAddr::new(ip, args.port)]);
+            }
+            match server.to_socket_addrs() {
+                Ok(addresses) => addresses.collect(),
+                Err(e) if e.kind() == io::ErrorKind::InvalidInput => (server.as_str(), args.port).to_socket_addrs()?.collect(),
+                Err(e) => return Err(e),
+            }
+        }
+        None => (args.ip.as_str(), args.port).to_socket_addrs()?.collect(),
+    };
+    Ok(addresses)
+}
+
+/// Connects to the server, all resolved addresses are tried until one accepts the connection.
+/// 
+/// The connect and read timeouts from the arguments are applied.
+pub fn connect(args: &Args) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in server_addresses(args)? {
+        let result = match args.connect_timeout {
+            Some(ms) => TcpStream::connect_timeout(&address, Duration::from_millis(ms)),
+            None => TcpStream::connect(address),
+        };
+        match result {
+            Ok(stream) => {
+                stream.set_read_timeout(args.read_timeout.map(Duration::from_millis))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the server address could not be resolved")))
+}
+
+/// Decides if and when the client connects again after the connection to the server was lost.
+/// 
+/// The delay is doubled after each failed attempt until `max_delay` is reached,
+/// a random jitter of up to half the delay is subtracted so that multiple clients don't reconnect at the same time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Number of attempts in a row after which the client gives up
+    max_attempts: u32,
+    /// Delay before the first attempt
+    base_delay: Duration,
+    /// Upper limit for the delay
+    max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+
+    /// Creates the reconnect policy from the command line arguments, returns `None` when reconnecting is disabled.
+    pub fn from_args(args: &Args) -> Option<Self> {
+        args.reconnect.map(|max_attempts| Self {
+            max_attempts,
+            base_delay: Duration::from_millis(args.reconnect_delay),
+            max_delay: Duration::from_millis(args.reconnect_max_delay),
+        })
+    }
+
+    /// Returns how long to wait before the attempt with the number `attempt` (starting at 0)
+    /// or `None` when no more attempts should be made.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_delay);
+        let jitter = thread_rng().gen_range(0.0..=0.5);
+        Some(delay.mul_f64(1.0 - jitter))
+    }
+}
+
+/// Name of a team as transmitted in the 16 byte name field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamName(String);
+
+impl TeamName {
+
+    /// Maximum number of bytes a team name can have
+    pub const MAX_LEN: usize = 16;
+
+    /// Creates a new team name.
+    /// 
+    /// Fails when the name is longer than 16 bytes or contains NUL bytes, as they are used for padding.
+    pub fn new(name: &str) -> Result<Self, ProtocolError> {
+        if name.len() > TeamName::MAX_LEN {
+            return Err(ProtocolError::OutOfRange { field: "team name length", value: name.len() as i64 });
+        }
+        if name.contains('\0') {
+            return Err(ProtocolError::Malformed { field: "team name", reason: String::from("contains NUL byte") });
+        }
+        Ok(Self(name.to_string()))
+    }
+
+    /// Decodes a team name from the name field, trailing NUL padding is removed and invalid UTF-8 is replaced.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        Self(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    /// Encodes the name into the 16 byte name field, padded with NUL bytes.
+    /// 
+    /// Names that were decoded with replacement characters can exceed 16 bytes, they are cut.
+    pub fn to_bytes(&self) -> [u8; TeamName::MAX_LEN] {
+        let mut bytes = [0u8; TeamName::MAX_LEN];
+        for (i, b) in self.0.bytes().take(TeamName::MAX_LEN).enumerate() {
+            bytes[i] = b;
+        }
+        bytes
+    }
+}
+
+impl Display for TeamName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 #[derive(Debug)]
 pub struct Register {
     client_type: u16,
-    team_name: String,
+    team_name: TeamName,
 }
 
 impl Register {
-    pub fn new(args: &Args) -> Self {
-        if args.team_name.len() > 16 {
-            panic!("Teamname to long, should be <= 16 chars long!");
-        }
-        Self {
-            client_type: CLIENT_TYPE, 
-            team_name: args.team_name.to_string(), 
-        }
+    /// Creates the registration message, fails when the team name is not valid.
+    /// 
+    /// With `--spectate` the client registers as observer.
+    pub fn new(args: &Args) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            client_type: if args.spectate { SPECTATOR_CLIENT_TYPE } else { CLIENT_TYPE }, 
+            team_name: TeamName::new(&args.team_name)?, 
+        })
     }    
     
+    /// Decodes a registration message as it is sent by a client.
+    pub fn from_bytes(bytes: &[u8; 18]) -> Self {
+        Self {
+            client_type: le_u16(&bytes[..2]),
+            team_name: TeamName::from_bytes(&bytes[2..]),
+        }
+    }
+
+    /// The name under which the team is registered
+    pub fn team_name(&self) -> &TeamName {
+        &self.team_name
+    }
+
+    /// Returns true if the client registered as observer
+    pub fn is_spectator(&self) -> bool {
+        self.client_type == SPECTATOR_CLIENT_TYPE
+    }
+
     pub fn as_bytes(&self) -> [u8; 18] {
-        let mut out: Vec<u8> = Vec::new();
-        out.push(self.client_type.try_into().unwrap());
-        out.push(0);
-        for b in self.team_name.as_bytes() {
-            out.push(*b);
+        let mut out = [0u8; 18];
+        out[..2].copy_from_slice(&self.client_type.to_le_bytes());
+        out[2..].copy_from_slice(&self.team_name.to_bytes());
+        out
+    }
+}
+
+impl Turn {
+
+    /// Creates a new `Turn` object by reading the next frame from the reader.
+    /// 
+    /// Returns `None` when the reader ended exactly at a frame boundary, this happens when the server closes the connection after the game is over.
+    /// 
+    /// The whole frame is read before it is decoded, so when decoding fails
+    /// the reader is still positioned at the start of the next frame.
+    pub fn new<R: Read>(input: &mut R) -> Result<Option<Self>, ProtocolError> {
+        match read_frame(input)? {
+            Some(frame) => Ok(Some(Turn::from_bytes(&frame)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes a `Turn` from a complete frame.
+    pub fn from_bytes(frame: &[u8]) -> Result<Self, ProtocolError> {
+        Ok(TurnView::new(frame)?.to_turn())
+    }
+
+    /// Serializes this turn into the byte format used by the server.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.objects.len() * OBJECT_LEN);
+        out.extend_from_slice(&self.team_id.to_le_bytes());
+        for team in &self.teams {
+            team.write_bytes(&mut out);
         }
-        for _i in self.team_name.len() .. 16 {
-            out.push(0);
+        out.extend_from_slice(&(self.objects.len() as u16).to_le_bytes());
+        for object in &self.objects {
+            object.write_bytes(&mut out);
         }
-        out.try_into().unwrap()
+        out
     }
+
+    /// Writes this turn in the byte format used by the server to the writer.
+    pub fn write_to<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        output.write_all(&self.to_bytes())
+    }
+
 }
 
-impl Turn {
+/// Borrowed view over a complete turn frame.
+/// 
+/// Teams and objects are decoded lazily from the frame when they are accessed,
+/// creating the view does not allocate.
+#[derive(Debug, Clone, Copy)]
+pub struct TurnView<'a> {
+    frame: &'a [u8],
+}
+
+impl<'a> TurnView<'a> {
 
-    /// Creates a new `Turn` object by parsing the bytes of the tcp stream
-    pub fn new(input: &mut Bytes<BufReader<TcpStream>>) -> Self {
-        // Parse team id
-        let team_id: i16 = i16::from_le_bytes(read_to_two_byte_array(input).unwrap());// Frage: Welche Größenordnung? Muss hier little endian oder big endian benutzt werden?
-        // Parse teams
-        let mut teams: Vec<Team> = Vec::new();
-        for i in 0..16 {
-            let team = Team::new(input, i);
-            teams.push(team);
-        }
-        // Parse number of objects
-        let nr_of_objects = u16::from_le_bytes(read_to_two_byte_array(input).unwrap());
-        let mut objects: Vec<Object> = Vec::new();
-        for _i in 0..nr_of_objects {
-            objects.push(Object::new(input));
+    /// Creates a view over the frame.
+    /// 
+    /// Checks that the frame length matches the number of objects and that the team id is valid.
+    pub fn new(frame: &'a [u8]) -> Result<Self, ProtocolError> {
+        if frame.len() < HEADER_LEN {
+            return Err(ProtocolError::UnexpectedEof);
         }
-        Self {
-            team_id,
-            teams,
-            _nr_of_objects: nr_of_objects,
-            objects
+        let view = Self { frame };
+        let expected_len = HEADER_LEN + usize::from(view.nr_of_objects()) * OBJECT_LEN;
+        if frame.len() != expected_len {
+            return Err(ProtocolError::Malformed {
+                field: "frame length",
+                reason: format!("expected {} bytes for {} objects, got {}", expected_len, view.nr_of_objects(), frame.len()),
+            });
+        }
+        if !(0..16).contains(&view.team_id()) {
+            return Err(ProtocolError::OutOfRange { field: "team id", value: i64::from(view.team_id()) });
+        }
+        Ok(view)
+    }
+
+    /// Team id of the client
+    pub fn team_id(&self) -> i16 {
+        i16::from_le_bytes([self.frame[0], self.frame[1]])// Frage: Welche Größenordnung? Muss hier little endian oder big endian benutzt werden?
+    }
+
+    /// Returns the team with the id.
+    /// 
+    /// # Panics
+    /// Panics when `id` is not below 16.
+    pub fn team(&self, id: usize) -> TeamView<'a> {
+        let start = 2 + id * TEAM_LEN;
+        TeamView {
+            id: id as i16,
+            bytes: &self.frame[start..start + TEAM_LEN],
+        }
+    }
+
+    /// Returns an iterator over all 16 teams.
+    pub fn teams(self) -> impl ExactSizeIterator<Item = TeamView<'a>> {
+        (0..16).map(move |id| self.team(id))
+    }
+
+    /// Number of objects contained in the frame
+    pub fn nr_of_objects(&self) -> u16 {
+        le_u16(&self.frame[HEADER_LEN - 2..])
+    }
+
+    /// Returns an iterator that decodes the objects of the frame.
+    pub fn objects(&self) -> impl ExactSizeIterator<Item = Object> + 'a {
+        self.frame[HEADER_LEN..].chunks_exact(OBJECT_LEN).map(Object::from_bytes)
+    }
+
+    /// Builds an owned `Turn` from this view.
+    pub fn to_turn(self) -> Turn {
+        Turn {
+            team_id: self.team_id(),
+            teams: self.teams().map(TeamView::to_team).collect(),
+            _nr_of_objects: self.nr_of_objects(),
+            objects: self.objects().collect(),
         }
     }
 
 }
 
-impl Team {
+/// Borrowed view over the bytes of a single team in a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct TeamView<'a> {
+    id: i16,
+    bytes: &'a [u8],
+}
 
-    /// Creates a new team by parsing the bytes in the tcp stream
-    fn new(bytes: &mut Bytes<BufReader<TcpStream>>, id: i16) -> Self {
-        Self {
-            id,
-            points: u16::from_le_bytes(read_to_two_byte_array(bytes).unwrap()),
-            _remaining_ants: u16::from_le_bytes(read_to_two_byte_array(bytes).unwrap()),
-            _team_name: bytes_to_string(bytes),
+impl<'a> TeamView<'a> {
+
+    /// Id of the team, this is also the index of its home base
+    pub fn id(&self) -> i16 {
+        self.id
+    }
+
+    /// Points of the team
+    pub fn points(&self) -> u16 {
+        le_u16(&self.bytes[0..2])
+    }
+
+    /// Number of ants the team has left
+    pub fn remaining_ants(&self) -> u16 {
+        le_u16(&self.bytes[2..4])
+    }
+
+    /// The raw 16 bytes of the team name
+    pub fn name_bytes(&self) -> &'a [u8] {
+        &self.bytes[4..TEAM_LEN]
+    }
+
+    /// Builds an owned `Team` from this view.
+    fn to_team(self) -> Team {
+        Team {
+            id: self.id,
+            points: self.points(),
+            remaining_ants: self.remaining_ants(),
+            team_name: TeamName::from_bytes(self.name_bytes()),
         }
     }
 
 }
 
+/// Reads the next complete frame from the reader.
+/// 
+/// The fixed size header is read first to learn the number of objects, the objects are then read in one go.
+/// 
+/// Returns `None` when the reader is at its end before the first byte of the frame,
+/// ending anywhere inside of the frame is reported as `ProtocolError::UnexpectedEof`.
+pub fn read_frame<R: Read>(input: &mut R) -> Result<Option<Vec<u8>>, ProtocolError> {
+    let mut frame = vec![0u8; HEADER_LEN];
+    let first = loop {
+        match input.read(&mut frame) {
+            Ok(0) => return Ok(None),
+            Ok(n) => break n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    input.read_exact(&mut frame[first..])?;
+    let nr_of_objects = usize::from(le_u16(&frame[HEADER_LEN - 2..]));
+    frame.resize(HEADER_LEN + nr_of_objects * OBJECT_LEN, 0);
+    input.read_exact(&mut frame[HEADER_LEN..])?;
+    Ok(Some(frame))
+}
+
+/// Reads frames from the server on a separate thread, so that frames that were superseded by a newer one can be skipped.
+#[derive(Debug)]
+pub struct FrameReader {
+    stream: TcpStream,
+    frames: Receiver<Result<Option<Vec<u8>>, ProtocolError>>,
+    /// Error that was received after frames that were returned first
+    error: Option<ProtocolError>,
+}
+
+impl FrameReader {
+
+    /// Starts reading frames from the stream.
+    pub fn spawn(stream: TcpStream) -> io::Result<Self> {
+        let mut input = BufReader::new(stream.try_clone()?);
+        let (sender, frames) = mpsc::channel();
+        thread::spawn(move || loop {
+            let result = read_frame(&mut input);
+            let done = !matches!(result, Ok(Some(_)));
+            // The reader has been dropped when sending fails
+            if sender.send(result).is_err() || done {
+                break;
+            }
+        });
+        Ok(Self { stream, frames, error: None })
+    }
+
+    /// Waits for the next frame and returns it together with all frames that were received after it, the newest frame is last.
+    /// 
+    /// Returns no frames when the server closed the connection between two frames.
+    pub fn next_frames(&mut self) -> Result<Vec<Vec<u8>>, ProtocolError> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let mut frames = Vec::new();
+        // A disconnected channel means that the reader thread has stopped after the last frame
+        let mut next = self.frames.recv().ok();
+        while let Some(result) = next {
+            match result {
+                Ok(Some(frame)) => frames.push(frame),
+                // The server closed the connection
+                Ok(None) => break,
+                Err(e) if frames.is_empty() => return Err(e),
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+            next = self.frames.try_recv().ok();
+        }
+        Ok(frames)
+    }
+}
+
+impl Drop for FrameReader {
+    fn drop(&mut self) {
+        // Stops the reader thread
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl Team {
+
+    /// Appends the bytes of this team to `out`.
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.points.to_le_bytes());
+        out.extend_from_slice(&self.remaining_ants.to_le_bytes());
+        out.extend_from_slice(&self.team_name.to_bytes());
+    }
+
+}
+
 impl Object {
 
-    /// Creates a new object by parsing the bytes in the tcp stream
-    fn new(input: &mut Bytes<BufReader<TcpStream>>) -> Self {
-        let b1 = Pair::new(input.next().unwrap().unwrap());
-        let b2 = Pair::new(input.next().unwrap().unwrap());
-        let x = u16::from_le_bytes(read_to_two_byte_array(input).unwrap());
-        let y = u16::from_le_bytes(read_to_two_byte_array(input).unwrap());
+    /// Creates a new object by parsing the 6 bytes that describe it
+    pub fn from_bytes(bytes: &[u8]) -> Self {
         Self {
-            b1,
-            b2,
-            pos: (x, y),
+            b1: Pair::new(bytes[0]),
+            b2: Pair::new(bytes[1]),
+            pos: (le_u16(&bytes[2..4]), le_u16(&bytes[4..6])),
         }
     }
+
+    /// Appends the bytes of this object to `out`.
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.push(self.b1.as_byte());
+        out.push(self.b2.as_byte());
+        out.extend_from_slice(&self.pos.0.to_le_bytes());
+        out.extend_from_slice(&self.pos.1.to_le_bytes());
+    }
 }
 
 impl Pair {
@@ -102,5 +511,202 @@ impl Pair {
             lower: byte & 0xf,
         }
     }
+
+    /// Packs the pair back into a single byte
+    fn as_byte(&self) -> u8 {
+        (self.upper << 4) | (self.lower & 0xf)
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{Cursor, Write}, time::Duration, net::{TcpListener, TcpStream}};
+
+    use clap::Parser;
+
+    use crate::{Turn, Object, Pair, ObjectKind, network::{ProtocolError, TurnView, TeamName, Register, ReconnectPolicy, FrameReader, server_addresses}, cli::Args};
+
+    /// Assembles a frame with the given team id, team points and objects.
+    fn frame(team_id: i16, points: &[u16; 16], objects: &[[u8; 6]]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&team_id.to_le_bytes());
+        for p in points {
+            bytes.extend_from_slice(&p.to_le_bytes());
+            bytes.extend_from_slice(&16u16.to_le_bytes());
+            bytes.extend_from_slice(b"team\0\0\0\0\0\0\0\0\0\0\0\0");
+        }
+        bytes.extend_from_slice(&(objects.len() as u16).to_le_bytes());
+        for o in objects {
+            bytes.extend_from_slice(o);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_consecutive_frames() {
+        let mut bytes = frame(1, &[0; 16], &[[0x11, 0x1a, 0x01, 0x00, 0x02, 0x00]]);
+        bytes.extend(frame(1, &[0; 16], &[]));
+        let mut cursor = Cursor::new(bytes);
+        assert_eq!(Turn::new(&mut cursor).unwrap().unwrap().objects.len(), 1);
+        assert_eq!(Turn::new(&mut cursor).unwrap().unwrap().objects.len(), 0);
+        assert!(matches!(Turn::new(&mut cursor), Ok(None)));
+    }
+
+    #[test]
+    fn test_frame_reader() {
+        let frames: Vec<Vec<u8>> = (0..3).map(|i| frame(1, &[i; 16], &[])).collect();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut server = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut reader = FrameReader::spawn(listener.accept().unwrap().0).unwrap();
+        for frame in &frames {
+            server.write_all(frame).unwrap();
+        }
+        server.write_all(&frames[0][..10]).unwrap();
+        drop(server);
+        let mut received = Vec::new();
+        loop {
+            match reader.next_frames() {
+                Ok(next) => {
+                    assert!(!next.is_empty());
+                    received.extend(next);
+                }
+                Err(e) => {
+                    assert!(matches!(e, ProtocolError::UnexpectedEof));
+                    break;
+                }
+            }
+        }
+        assert_eq!(received, frames);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut server = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut reader = FrameReader::spawn(listener.accept().unwrap().0).unwrap();
+        server.write_all(&frames[1]).unwrap();
+        assert_eq!(reader.next_frames().unwrap(), vec![frames[1].clone()]);
+        drop(server);
+        assert_eq!(reader.next_frames().unwrap(), Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    fn test_turn_from_cursor() {
+        let mut points = [0u16; 16];
+        points[4] = 300;
+        let bytes = frame(3, &points, &[[0x13, 0x2a, 0x64, 0x00, 0xc8, 0x00], [0x20, 0x00, 0xe8, 0x03, 0x01, 0x00]]);
+        let turn = Turn::new(&mut Cursor::new(bytes)).unwrap().unwrap();
+        assert_eq!(turn.team_id, 3);
+        assert_eq!(turn.teams.len(), 16);
+        assert_eq!(turn.teams[4].points, 300);
+        assert_eq!(turn.objects.len(), 2);
+        assert_eq!(turn.objects[0].kind(), ObjectKind::Ant);
+        assert_eq!(turn.objects[0].b1.lower, 3);
+        assert_eq!(turn.objects[0].b2.upper, 2);
+        assert_eq!(turn.objects[0].b2.lower, 10);
+        assert_eq!(turn.objects[0].pos, (100, 200));
+        assert_eq!(turn.objects[1].kind(), ObjectKind::Sugar);
+        assert_eq!(turn.objects[1].pos, (1000, 1));
+    }
+
+    #[test]
+    fn test_turn_errors() {
+        let bytes = frame(3, &[0; 16], &[[0x13, 0x2a, 0x64, 0x00, 0xc8, 0x00]]);
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(matches!(Turn::new(&mut Cursor::new(truncated)), Err(ProtocolError::UnexpectedEof)));
+        assert!(matches!(Turn::new(&mut Cursor::new(&bytes[..1])), Err(ProtocolError::UnexpectedEof)));
+        let mut cursor = Cursor::new(frame(16, &[0; 16], &[]));
+        assert!(matches!(Turn::new(&mut cursor), Err(ProtocolError::OutOfRange { field: "team id", value: 16 })));
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+        let mut too_long = bytes.clone();
+        too_long.push(0);
+        assert!(matches!(Turn::from_bytes(&too_long), Err(ProtocolError::Malformed { field: "frame length", .. })));
+    }
+
+    #[test]
+    fn test_turn_view() {
+        let mut points = [0u16; 16];
+        points[9] = 42;
+        let bytes = frame(5, &points, &[[0x15, 0x3a, 0x64, 0x00, 0xc8, 0x00], [0x20, 0x00, 0x0a, 0x00, 0x0b, 0x00]]);
+        let view = TurnView::new(&bytes).unwrap();
+        assert_eq!(view.team_id(), 5);
+        assert_eq!(view.teams().len(), 16);
+        assert_eq!(view.team(9).points(), 42);
+        assert_eq!(view.team(9).remaining_ants(), 16);
+        assert_eq!(view.team(9).name_bytes(), b"team\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(view.nr_of_objects(), 2);
+        let positions: Vec<(u16, u16)> = view.objects().map(|o| o.pos).collect();
+        assert_eq!(positions, vec![(100, 200), (10, 11)]);
+        assert_eq!(view.to_turn(), Turn::from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_turn_round_trip() {
+        let mut points = [0u16; 16];
+        points[0] = 7;
+        points[15] = 65535;
+        let bytes = frame(15, &points, &[[0x13, 0x2a, 0x64, 0x00, 0xc8, 0x00], [0x4f, 0x00, 0xe7, 0x03, 0xe7, 0x03]]);
+        let turn = Turn::new(&mut Cursor::new(&bytes)).unwrap().unwrap();
+        assert_eq!(turn.to_bytes(), bytes);
+
+        let turn = Turn::test(2, vec![Object { b1: Pair { upper: 3, lower: 2 }, b2: Pair { upper: 11, lower: 9 }, pos: (512, 300) }]).with_teams(|team| {
+            team.points = team.id as u16 * 10;
+            team.team_name = TeamName::new("Rust_pirates").unwrap();
+        });
+        let mut bytes = Vec::new();
+        turn.write_to(&mut bytes).unwrap();
+        assert_eq!(Turn::new(&mut Cursor::new(bytes)).unwrap().unwrap(), turn);
+    }
+
+    #[test]
+    fn test_team_name() {
+        assert_eq!(TeamName::new("Rust_pirates").unwrap().to_bytes(), *b"Rust_pirates\0\0\0\0");
+        let umlauts = TeamName::new("ÄÖÜäöüß_x").unwrap();
+        assert_eq!(TeamName::from_bytes(&umlauts.to_bytes()), umlauts);
+        assert!(matches!(TeamName::new("ÄÖÜäöüß_xy"), Err(ProtocolError::OutOfRange { field: "team name length", value: 17 })));
+        assert!(matches!(TeamName::new("a\0b"), Err(ProtocolError::Malformed { .. })));
+        assert_eq!(TeamName::from_bytes(b"ants\0\0\0\0\0\0\0\0\0\0\0\0").to_string(), "ants");
+        assert_eq!(TeamName::from_bytes(b"0123456789abcdef").to_string(), "0123456789abcdef");
+        assert_eq!(TeamName::from_bytes(b"ant\xffs\0\0\0\0\0\0\0\0\0\0\0").to_string(), "ant\u{fffd}s");
+        let register = Register::new(&Args::parse_from(["tondorf_ants", "-d", "-t", "ants"])).unwrap();
+        assert_eq!(register.as_bytes(), *b"\x01\0ants\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(Register::from_bytes(&register.as_bytes()).as_bytes(), register.as_bytes());
+        assert!(Register::new(&Args::parse_from(["tondorf_ants", "-d", "-t", "a_very_long_team_name"])).is_err());
+    }
+
+    #[test]
+    fn test_spectator_register() {
+        let spectator = Register::new(&Args::parse_from(["tondorf_ants", "--spectate", "-t", "ants"])).unwrap();
+        assert_eq!(spectator.as_bytes(), *b"\0\0ants\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(Register::from_bytes(&spectator.as_bytes()).as_bytes(), spectator.as_bytes());
+        assert!(spectator.is_spectator());
+        assert!(!Register::new(&Args::parse_from(["tondorf_ants", "-d", "-t", "ants"])).unwrap().is_spectator());
+    }
+
+    #[test]
+    fn test_server_addresses() {
+        let addresses = |args: &[&str]| {
+            let mut all = vec!["tondorf_ants", "-d"];
+            all.extend_from_slice(args);
+            server_addresses(&Args::parse_from(all)).unwrap()
+        };
+        assert_eq!(addresses(&[]), vec!["127.0.0.1:5000".parse().unwrap()]);
+        assert_eq!(addresses(&["-i", "::1", "--port", "6000"]), vec!["[::1]:6000".parse().unwrap()]);
+        assert_eq!(addresses(&["--server", "[::1]:7000"]), vec!["[::1]:7000".parse().unwrap()]);
+        assert_eq!(addresses(&["--server", "[::1]:5000", "--port", "7000"]), vec!["[::1]:5000".parse().unwrap()]);
+        assert_eq!(addresses(&["--server", "::1"]), vec!["[::1]:5000".parse().unwrap()]);
+        assert_eq!(addresses(&["--server", "[::1]", "--port", "7000"]), vec!["[::1]:7000".parse().unwrap()]);
+        assert_eq!(addresses(&["--server", "10.0.0.1", "--port", "7000"]), vec!["10.0.0.1:7000".parse().unwrap()]);
+        assert!(addresses(&["--host", "localhost"]).iter().all(|a| a.ip().is_loopback() && a.port() == 5000));
+    }
+
+    #[test]
+    fn test_reconnect_policy() {
+        assert_eq!(ReconnectPolicy::from_args(&Args::parse_from(["tondorf_ants", "-d"])), None);
+        let policy = ReconnectPolicy::from_args(&Args::parse_from(["tondorf_ants", "-d", "--reconnect", "5", "--reconnect-delay", "100", "--reconnect-max-delay", "500"])).unwrap();
+        for (attempt, max) in [(0, 100), (1, 200), (2, 400), (3, 500), (4, 500)] {
+            let delay = policy.delay(attempt).unwrap();
+            assert!(delay <= Duration::from_millis(max), "attempt {}: {:?}", attempt, delay);
+            assert!(delay >= Duration::from_millis(max / 2), "attempt {}: {:?}", attempt, delay);
+        }
+        assert_eq!(policy.delay(5), None);
+    }
+}
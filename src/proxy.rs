@@ -0,0 +1,205 @@
+use std::{io::{self, BufReader, Read, Seek, Write}, net::{Shutdown, TcpStream}, sync::mpsc::{self, Sender}, thread, fmt::{self, Display}};
+
+use crate::{cli::Args, network::{connect, read_frame, ProtocolError, Register}, recording::{Recorder, RecordingHeader, RecordedTurn, NO_ACTIONS, timestamp_now}};
+
+/// Data that was forwarded by one of the two directions of the proxy
+enum Message {
+    /// A frame that was sent from the server to the client and the time it was received
+    Frame(u64, Vec<u8>),
+    /// Actions that were sent from the client to the server
+    Actions([u8; 16]),
+}
+
+/// Statistics about a game that was forwarded by the proxy.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProxyStats {
+    /// Number of turns that were recorded
+    turns: u64,
+    /// Number of turns the client did not send actions for
+    unanswered_turns: u64,
+    /// Number of action messages that were sent without a turn they belong to
+    unexpected_actions: u64,
+}
+
+impl Display for ProxyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Recorded {} turns:", self.turns)?;
+        writeln!(f, " Turns without actions: {}", self.unanswered_turns)?;
+        write!(f, " Actions without turn: {}", self.unexpected_actions)
+    }
+}
+
+/// Forwards everything between the client and the server until one of them closes the connection.
+///
+/// The registration of the client is read first, then the connection to the server from `args` is established.
+/// Each turn is recorded together with the actions the client answered with.
+pub fn proxy<W: Write + Seek>(mut client: TcpStream, args: &Args, output: W) -> Result<ProxyStats, ProtocolError> {
+    let mut registration = [0u8; 18];
+    client.read_exact(&mut registration)?;
+    let register = Register::from_bytes(&registration);
+    println!("Client registered as {}", register.team_name());
+    let mut server = connect(args)?;
+    server.write_all(&registration)?;
+    let header = RecordingHeader {
+        client_version: env!("CARGO_PKG_VERSION").to_string(),
+        team_name: register.team_name().clone(),
+        jobs: [None; 16],
+        seed: None,
+    };
+    let mut recorder = Recorder::new(output, &header)?;
+
+    let (sender, receiver) = mpsc::channel();
+    let downstream = {
+        let from_server = server.try_clone()?;
+        let to_client = client.try_clone()?;
+        let sender = sender.clone();
+        thread::spawn(move || forward_frames(from_server, to_client, sender))
+    };
+    let upstream = thread::spawn(move || forward_actions(client, server, sender));
+
+    let mut stats = ProxyStats::default();
+    let mut pending: Option<(u64, Vec<u8>)> = None;
+    for message in receiver {
+        match message {
+            Message::Frame(timestamp, frame) => {
+                if let Some((timestamp, frame)) = pending.replace((timestamp, frame)) {
+                    recorder.record(&RecordedTurn { timestamp, frame, actions: NO_ACTIONS })?;
+                    stats.unanswered_turns += 1;
+                    stats.turns += 1;
+                }
+            }
+            Message::Actions(actions) => match pending.take() {
+                Some((timestamp, frame)) => {
+                    recorder.record(&RecordedTurn { timestamp, frame, actions })?;
+                    stats.turns += 1;
+                }
+                None => stats.unexpected_actions += 1,
+            },
+        }
+    }
+    if let Some((timestamp, frame)) = pending {
+        recorder.record(&RecordedTurn { timestamp, frame, actions: NO_ACTIONS })?;
+        stats.unanswered_turns += 1;
+        stats.turns += 1;
+    }
+    recorder.finish()?;
+    for (direction, handle) in [("server", downstream), ("client", upstream)] {
+        match handle.join() {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => println!("Connection to {} failed: {}", direction, e),
+            Err(_) => println!("Forwarding data from the {} failed", direction),
+        }
+    }
+    Ok(stats)
+}
+
+/// Forwards frames from the server to the client until the server closes the connection,
+/// the connection to the client is closed afterwards.
+fn forward_frames(from_server: TcpStream, mut to_client: TcpStream, sender: Sender<Message>) -> Result<(), ProtocolError> {
+    let mut from_server = BufReader::new(from_server);
+    let result = loop {
+        match read_frame(&mut from_server) {
+            Ok(Some(frame)) => {
+                // Queued before forwarding, so that the answer of the client can't overtake the frame.
+                // The recording has already stopped when sending fails
+                let _ = sender.send(Message::Frame(timestamp_now(), frame.clone()));
+                if let Err(e) = to_client.write_all(&frame) {
+                    break Err(e.into());
+                }
+            }
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+    let _ = to_client.shutdown(Shutdown::Both);
+    result
+}
+
+/// Forwards actions from the client to the server until the client closes the connection,
+/// the connection to the server is closed afterwards.
+fn forward_actions(mut from_client: TcpStream, mut to_server: TcpStream, sender: Sender<Message>) -> Result<(), ProtocolError> {
+    let result = loop {
+        let mut actions = [0u8; 16];
+        match from_client.read_exact(&mut actions) {
+            Ok(()) => (),
+            // The client closed the connection
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break Ok(()),
+            Err(e) => break Err(e.into()),
+        }
+        // Queued before forwarding, so that the next frame of the server can't overtake the actions
+        let _ = sender.send(Message::Actions(actions));
+        if let Err(e) = to_server.write_all(&actions) {
+            break Err(e.into());
+        }
+    };
+    let _ = to_server.shutdown(Shutdown::Both);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{Cursor, Read, Write}, net::{TcpListener, TcpStream}, thread};
+
+    use clap::Parser;
+
+    use crate::{Turn, cli::Args, network::TeamName, recording::RecordingReader};
+
+    use super::{proxy, ProxyStats};
+
+    #[test]
+    fn test_proxy() {
+        let turn = Turn::test(7, Vec::new()).with_teams(|team| team.team_name = TeamName::new("other").unwrap());
+        let frame = turn.to_bytes();
+        let server_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_address = server_listener.local_addr().unwrap().to_string();
+        let server_frame = frame.clone();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = server_listener.accept().unwrap();
+            let mut registration = [0u8; 18];
+            stream.read_exact(&mut registration).unwrap();
+            for i in 0..3 {
+                stream.write_all(&server_frame).unwrap();
+                let mut actions = [0u8; 16];
+                stream.read_exact(&mut actions).unwrap();
+                assert_eq!(actions, [i + 1; 16]);
+            }
+            // The client does not answer the last turn
+            stream.write_all(&server_frame).unwrap();
+            registration
+        });
+
+        let proxy_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_address = proxy_listener.local_addr().unwrap();
+        let client_frame = frame.clone();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(proxy_address).unwrap();
+            stream.write_all(b"\x01\0Other_bot\0\0\0\0\0\0\0").unwrap();
+            for i in 0..3 {
+                let mut received = vec![0u8; client_frame.len()];
+                stream.read_exact(&mut received).unwrap();
+                assert_eq!(received, client_frame);
+                stream.write_all(&[i + 1; 16]).unwrap();
+            }
+            let mut rest = Vec::new();
+            stream.read_to_end(&mut rest).unwrap();
+        });
+
+        let (client_stream, _) = proxy_listener.accept().unwrap();
+        let args = Args::parse_from(["tondorf_ants", "--server", &server_address, "proxy", "game.rec"]);
+        let mut output = Cursor::new(Vec::new());
+        let stats = proxy(client_stream, &args, &mut output).unwrap();
+        assert_eq!(&server.join().unwrap(), b"\x01\0Other_bot\0\0\0\0\0\0\0");
+        client.join().unwrap();
+        assert_eq!(stats, ProxyStats { turns: 4, unanswered_turns: 1, unexpected_actions: 0 });
+
+        let mut reader = RecordingReader::new(Cursor::new(output.into_inner())).unwrap();
+        assert_eq!(reader.header().team_name, TeamName::new("Other_bot").unwrap());
+        assert_eq!(reader.header().jobs, [None; 16]);
+        for actions in [[1; 16], [2; 16], [3; 16], [0; 16]] {
+            let recorded = reader.next_turn().unwrap().unwrap();
+            assert_eq!(recorded.frame, frame);
+            assert_eq!(recorded.actions, actions);
+        }
+        assert_eq!(reader.next_turn().unwrap(), None);
+    }
+}
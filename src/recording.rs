@@ -0,0 +1,482 @@
+//! A recording starts with a header that is followed by one entry per turn.
+//! When the recording was finished properly an index with the position of every entry follows the entries,
+//! otherwise the entries continue until the end of the file.
+//! All numbers are stored little endian.
+//!
+//! Header:
+//!
+//! | Size     | Content                                                                          |
+//! |----------|----------------------------------------------------------------------------------|
+//! | 8 bytes  | Magic bytes `TANTSREC`                                                           |
+//! | 2 bytes  | Version of the recording format, currently 3                                     |
+//! | 1 byte   | Length `n` of the client version                                                 |
+//! | n bytes  | Version of the client that made the recording as UTF-8                           |
+//! | 16 bytes | Name of the team, padded with NUL bytes                                          |
+//! | 16 bytes | Job of each ant: 0 = gatherer, 1 = offensive, 2 = waste mover, 255 = unknown     |
+//! | 1 byte   | 1 if the seed of the AI is known, otherwise 0, since version 3                  |
+//! | 8 bytes  | Seed of the AI or 0 if it is not known, since version 3                          |
+//! | 8 bytes  | Position of the index in the file or 0 if there is no index, since version 2     |
+//!
+//! Entry:
+//!
+//! | Size     | Content                                                                          |
+//! |----------|----------------------------------------------------------------------------------|
+//! | 8 bytes  | Time the frame was received in milliseconds since the unix epoch                 |
+//! | 4 bytes  | Length `n` of the frame                                                          |
+//! | n bytes  | The turn frame exactly as it was received from the server                        |
+//! | 16 bytes | The actions that were sent back to the server, one byte per ant, 0 if none sent  |
+//!
+//! Turns that were not answered, for example frames that were skipped to catch up with the server, have all actions set to 0.
+//!
+//! Index:
+//!
+//! | Size       | Content                                                                        |
+//! |------------|--------------------------------------------------------------------------------|
+//! | 8 bytes    | Number `n` of entries                                                          |
+//! | n*8 bytes  | Position of each entry in the file                                             |
+
+use std::{io::{self, Read, Write, Seek, SeekFrom, BufReader, BufWriter}, fs::File, path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{AntJob, network::{TeamName, ProtocolError}};
+
+/// Magic bytes at the start of every recording
+const MAGIC: &[u8; 8] = b"TANTSREC";
+/// Version of the recording format that is written
+const FORMAT_VERSION: u16 = 3;
+/// First version of the recording format that contains an index
+const INDEX_VERSION: u16 = 2;
+/// First version of the recording format that contains the seed of the AI
+const SEED_VERSION: u16 = 3;
+/// Number of bytes of an entry that are not part of the frame: timestamp, frame length and actions
+const ENTRY_OVERHEAD: u64 = 8 + 4 + 16;
+/// Byte used to store the job of an ant when it is not known
+const UNKNOWN_JOB: u8 = 255;
+
+/// Actions that are recorded when a turn was not answered
+pub const NO_ACTIONS: [u8; 16] = [0; 16];
+
+/// Information about the game that is stored at the start of a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingHeader {
+    /// Version of the client that made the recording
+    pub client_version: String,
+    /// Name of the team the recording was made for
+    pub team_name: TeamName,
+    /// Job of each ant, `None` if the job is not known
+    pub jobs: [Option<AntJob>; 16],
+    /// Seed of the generator the AI drew its random decisions from, `None` if it is not known
+    pub seed: Option<u64>,
+}
+
+impl RecordingHeader {
+
+    /// Creates the header for a recording made by this client.
+    pub fn new(team_name: TeamName, ant_jobs: &[AntJob], seed: Option<u64>) -> Self {
+        let mut jobs = [None; 16];
+        for (job, ant_job) in jobs.iter_mut().zip(ant_jobs) {
+            *job = Some(*ant_job);
+        }
+        Self {
+            client_version: env!("CARGO_PKG_VERSION").to_string(),
+            team_name,
+            jobs,
+            seed,
+        }
+    }
+
+    /// Writes the header to the output.
+    fn write_to<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        output.write_all(MAGIC)?;
+        output.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let version = self.client_version.as_bytes();
+        let version = &version[..version.len().min(u8::MAX as usize)];
+        output.write_all(&[version.len() as u8])?;
+        output.write_all(version)?;
+        output.write_all(&self.team_name.to_bytes())?;
+        for job in &self.jobs {
+            output.write_all(&[job.map_or(UNKNOWN_JOB, job_to_byte)])?;
+        }
+        output.write_all(&[u8::from(self.seed.is_some())])?;
+        output.write_all(&self.seed.unwrap_or(0).to_le_bytes())?;
+        // The index is not yet written
+        output.write_all(&0u64.to_le_bytes())
+    }
+
+    /// Reads the header from the input.
+    /// 
+    /// Returns the header, the position of the index (0 if there is none) and the length of the header.
+    fn read_from<R: Read>(input: &mut R) -> Result<(Self, u64, u64), ProtocolError> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(ProtocolError::Malformed { field: "recording", reason: String::from("not a recording, magic bytes are missing") });
+        }
+        let mut version = [0u8; 2];
+        input.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(ProtocolError::OutOfRange { field: "recording format version", value: i64::from(version) });
+        }
+        let mut len = [0u8; 1];
+        input.read_exact(&mut len)?;
+        let mut client_version = vec![0u8; usize::from(len[0])];
+        input.read_exact(&mut client_version)?;
+        let mut team_name = [0u8; 16];
+        input.read_exact(&mut team_name)?;
+        let mut job_bytes = [0u8; 16];
+        input.read_exact(&mut job_bytes)?;
+        let mut jobs = [None; 16];
+        for (job, byte) in jobs.iter_mut().zip(job_bytes) {
+            *job = job_from_byte(byte)?;
+        }
+        let mut header_len = (8 + 2 + 1 + client_version.len() + 16 + 16) as u64;
+        let mut seed = None;
+        if version >= SEED_VERSION {
+            let mut known = [0u8; 1];
+            input.read_exact(&mut known)?;
+            let mut bytes = [0u8; 8];
+            input.read_exact(&mut bytes)?;
+            seed = match known[0] {
+                0 => None,
+                1 => Some(u64::from_le_bytes(bytes)),
+                other => return Err(ProtocolError::OutOfRange { field: "recording seed flag", value: i64::from(other) }),
+            };
+            header_len += 9;
+        }
+        let mut index_offset = 0;
+        if version >= INDEX_VERSION {
+            let mut offset = [0u8; 8];
+            input.read_exact(&mut offset)?;
+            index_offset = u64::from_le_bytes(offset);
+            header_len += 8;
+        }
+        let header = Self {
+            client_version: String::from_utf8_lossy(&client_version).into_owned(),
+            team_name: TeamName::from_bytes(&team_name),
+            jobs,
+            seed,
+        };
+        Ok((header, index_offset, header_len))
+    }
+}
+
+/// A single turn of a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedTurn {
+    /// Time the frame was received in milliseconds since the unix epoch
+    pub timestamp: u64,
+    /// The frame as it was received from the server
+    pub frame: Vec<u8>,
+    /// The actions that were sent back to the server
+    pub actions: [u8; 16],
+}
+
+/// Writes turns into a recording.
+/// 
+/// The index is only written when the recording is finished with [`Recorder::finish`].
+#[derive(Debug)]
+pub struct Recorder<W: Write + Seek> {
+    output: W,
+    /// Position of the header field that stores the position of the index
+    index_offset_field: u64,
+    /// Position of each entry that was written
+    offsets: Vec<u64>,
+}
+
+impl Recorder<BufWriter<File>> {
+
+    /// Creates the file at `path` and writes the header to it.
+    pub fn create<P: AsRef<Path>>(path: P, header: &RecordingHeader) -> io::Result<Self> {
+        Recorder::new(BufWriter::new(File::create(path)?), header)
+    }
+}
+
+impl<W: Write + Seek> Recorder<W> {
+
+    /// Creates a new recorder that writes the header to the output.
+    pub fn new(mut output: W, header: &RecordingHeader) -> io::Result<Self> {
+        header.write_to(&mut output)?;
+        output.flush()?;
+        let index_offset_field = output.stream_position()? - 8;
+        Ok(Self {
+            output,
+            index_offset_field,
+            offsets: Vec::new(),
+        })
+    }
+
+    /// Appends a turn to the recording.
+    ///
+    /// The output is flushed, so that the recording is complete even if the client crashes.
+    pub fn record(&mut self, turn: &RecordedTurn) -> io::Result<()> {
+        self.offsets.push(self.output.stream_position()?);
+        self.output.write_all(&turn.timestamp.to_le_bytes())?;
+        self.output.write_all(&(turn.frame.len() as u32).to_le_bytes())?;
+        self.output.write_all(&turn.frame)?;
+        self.output.write_all(&turn.actions)?;
+        self.output.flush()
+    }
+
+    /// Writes the index behind the last entry and stores its position in the header.
+    /// 
+    /// Returns the output.
+    pub fn finish(mut self) -> io::Result<W> {
+        let index_offset = self.output.stream_position()?;
+        self.output.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        for offset in &self.offsets {
+            self.output.write_all(&offset.to_le_bytes())?;
+        }
+        self.output.seek(SeekFrom::Start(self.index_offset_field))?;
+        self.output.write_all(&index_offset.to_le_bytes())?;
+        self.output.seek(SeekFrom::End(0))?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+/// Reads the turns of a recording.
+#[derive(Debug)]
+pub struct RecordingReader<R: Read> {
+    input: R,
+    header: RecordingHeader,
+    /// Position of the index or 0 if the recording has no index
+    index_offset: u64,
+    /// Position of the first entry
+    entries_start: u64,
+    /// Current position in the recording
+    position: u64,
+    /// Position of each entry, loaded when it is needed for the first time
+    index: Option<Vec<u64>>,
+}
+
+impl RecordingReader<BufReader<File>> {
+
+    /// Opens the recording at `path` and reads its header.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ProtocolError> {
+        RecordingReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> RecordingReader<R> {
+
+    /// Creates a new reader, the header is read immediately.
+    /// 
+    /// The input has to be positioned at the start of the recording.
+    pub fn new(mut input: R) -> Result<Self, ProtocolError> {
+        let (header, index_offset, header_len) = RecordingHeader::read_from(&mut input)?;
+        Ok(Self {
+            input,
+            header,
+            index_offset,
+            entries_start: header_len,
+            position: header_len,
+            index: None,
+        })
+    }
+
+    /// The header of the recording
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+
+    /// Reads the next turn, returns `None` at the end of the recording.
+    pub fn next_turn(&mut self) -> Result<Option<RecordedTurn>, ProtocolError> {
+        let mut timestamp = [0u8; 8];
+        if !self.read_entry_start(&mut timestamp)? {
+            return Ok(None);
+        }
+        let mut len = [0u8; 4];
+        self.input.read_exact(&mut len)?;
+        let mut frame = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.input.read_exact(&mut frame)?;
+        let mut actions = [0u8; 16];
+        self.input.read_exact(&mut actions)?;
+        self.position += ENTRY_OVERHEAD + frame.len() as u64;
+        Ok(Some(RecordedTurn {
+            timestamp: u64::from_le_bytes(timestamp),
+            frame,
+            actions,
+        }))
+    }
+
+    /// Reads the timestamp of the next entry, returns `false` when there are no more entries.
+    fn read_entry_start(&mut self, timestamp: &mut [u8; 8]) -> Result<bool, ProtocolError> {
+        if self.index_offset != 0 && self.position >= self.index_offset {
+            return Ok(false);
+        }
+        match self.input.read(&mut timestamp[..1]) {
+            Ok(0) => return Ok(false),
+            Ok(_) => (),
+            Err(e) => return Err(e.into()),
+        }
+        self.input.read_exact(&mut timestamp[1..])?;
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> RecordingReader<R> {
+
+    /// Moves the reader so that the next call of [`RecordingReader::next_turn`] returns the turn with the number `nr`, turns are counted from 0.
+    /// 
+    /// The index is used if the recording has one, otherwise the entries are skipped one by one.
+    /// Returns `false` when the recording has less turns.
+    pub fn seek_to_turn(&mut self, nr: u64) -> Result<bool, ProtocolError> {
+        if self.index_offset != 0 {
+            let offset = match self.index()?.get(nr as usize) {
+                Some(offset) => *offset,
+                None => return Ok(false),
+            };
+            self.position = self.input.seek(SeekFrom::Start(offset))?;
+            return Ok(true);
+        }
+        self.position = self.input.seek(SeekFrom::Start(self.entries_start))?;
+        for _i in 0..nr {
+            let mut timestamp = [0u8; 8];
+            if !self.read_entry_start(&mut timestamp)? {
+                return Ok(false);
+            }
+            let mut len = [0u8; 4];
+            self.input.read_exact(&mut len)?;
+            let len = u64::from(u32::from_le_bytes(len));
+            self.position = self.input.seek(SeekFrom::Current((len + 16) as i64))?;
+        }
+        let mut timestamp = [0u8; 8];
+        let more = self.read_entry_start(&mut timestamp)?;
+        self.position = self.input.seek(SeekFrom::Start(self.position))?;
+        Ok(more)
+    }
+
+    /// Returns the position of every entry, the index is read from the recording the first time this is called.
+    fn index(&mut self) -> Result<&[u64], ProtocolError> {
+        if self.index.is_none() {
+            self.input.seek(SeekFrom::Start(self.index_offset))?;
+            let mut count = [0u8; 8];
+            self.input.read_exact(&mut count)?;
+            let mut index = Vec::new();
+            for _i in 0..u64::from_le_bytes(count) {
+                let mut offset = [0u8; 8];
+                self.input.read_exact(&mut offset)?;
+                index.push(u64::from_le_bytes(offset));
+            }
+            self.input.seek(SeekFrom::Start(self.position))?;
+            self.index = Some(index);
+        }
+        Ok(self.index.as_deref().unwrap_or_default())
+    }
+}
+
+/// Returns the current time in milliseconds since the unix epoch.
+pub fn timestamp_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Converts the job into the byte that is stored in the header.
+fn job_to_byte(job: AntJob) -> u8 {
+    match job {
+        AntJob::Gatherer => 0,
+        AntJob::Offensive => 1,
+        AntJob::WasteMover => 2,
+    }
+}
+
+/// Converts the byte stored in the header into the job.
+fn job_from_byte(byte: u8) -> Result<Option<AntJob>, ProtocolError> {
+    match byte {
+        0 => Ok(Some(AntJob::Gatherer)),
+        1 => Ok(Some(AntJob::Offensive)),
+        2 => Ok(Some(AntJob::WasteMover)),
+        UNKNOWN_JOB => Ok(None),
+        _ => Err(ProtocolError::OutOfRange { field: "ant job", value: i64::from(byte) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{AntJob, network::{TeamName, ProtocolError}};
+
+    use super::{Recorder, RecordingHeader, RecordedTurn, RecordingReader};
+
+    /// Creates a header with fixed client version
+    fn header() -> RecordingHeader {
+        let mut jobs = vec![AntJob::Gatherer; 8];
+        jobs.extend([AntJob::Offensive; 6]);
+        jobs.extend([AntJob::WasteMover; 2]);
+        let mut header = RecordingHeader::new(TeamName::new("Rust_pirates").unwrap(), &jobs, Some(42));
+        header.client_version = String::from("0.1.0");
+        header
+    }
+
+    #[test]
+    fn test_recording_round_trip() {
+        let header = header();
+        let mut recorder = Recorder::new(Cursor::new(Vec::new()), &header).unwrap();
+        recorder.record(&RecordedTurn { timestamp: 258, frame: vec![1, 2, 3], actions: [5; 16] }).unwrap();
+
+        let mut expected = b"TANTSREC\x03\x00\x050.1.0Rust_pirates\0\0\0\0".to_vec();
+        expected.extend([0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 2, 2]);
+        expected.extend([1, 42, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend([0; 8]);
+        expected.extend([2, 1, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 1, 2, 3]);
+        expected.extend([5; 16]);
+        assert_eq!(recorder.output.get_ref(), &expected);
+
+        let mut reader = RecordingReader::new(Cursor::new(&expected)).unwrap();
+        assert_eq!(reader.header(), &header);
+        assert_eq!(reader.next_turn().unwrap(), Some(RecordedTurn { timestamp: 258, frame: vec![1, 2, 3], actions: [5; 16] }));
+        assert_eq!(reader.next_turn().unwrap(), None);
+        let mut reader = RecordingReader::new(Cursor::new(&expected[..expected.len() - 1])).unwrap();
+        assert!(matches!(reader.next_turn(), Err(ProtocolError::UnexpectedEof)));
+        assert!(matches!(RecordingReader::new(Cursor::new(&expected[1..])), Err(ProtocolError::Malformed { .. })));
+
+        // The seed is followed by the index position and the entry
+        let seed_start = expected.len() - 31 - 8 - 9;
+        let mut invalid_seed = expected.clone();
+        invalid_seed[seed_start] = 2;
+        assert!(matches!(RecordingReader::new(Cursor::new(&invalid_seed)), Err(ProtocolError::OutOfRange { .. })));
+
+        // Recordings of version 2 have no seed, recordings of version 1 have no index either
+        let header = RecordingHeader { seed: None, ..header };
+        for (version, skipped) in [(2, 9), (1, 17)] {
+            let mut old = expected[..8].to_vec();
+            old.extend([version, 0]);
+            old.extend(&expected[10..seed_start]);
+            old.extend(&expected[seed_start + skipped..]);
+            let mut reader = RecordingReader::new(Cursor::new(&old)).unwrap();
+            assert_eq!(reader.header(), &header);
+            assert_eq!(reader.next_turn().unwrap().unwrap().timestamp, 258);
+            assert_eq!(reader.next_turn().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_recording_index() {
+        let turns: Vec<RecordedTurn> = (0..20).map(|i| RecordedTurn { timestamp: i, frame: vec![i as u8; i as usize], actions: [i as u8; 16] }).collect();
+        let mut recorder = Recorder::new(Cursor::new(Vec::new()), &header()).unwrap();
+        for turn in &turns {
+            recorder.record(turn).unwrap();
+        }
+        let unfinished = recorder.output.get_ref().clone();
+        let finished = recorder.finish().unwrap().into_inner();
+        assert!(finished.len() > unfinished.len());
+
+        for bytes in [finished, unfinished] {
+            let mut reader = RecordingReader::new(Cursor::new(bytes)).unwrap();
+            assert!(reader.seek_to_turn(13).unwrap());
+            assert_eq!(reader.next_turn().unwrap().as_ref(), Some(&turns[13]));
+            assert!(reader.seek_to_turn(2).unwrap());
+            assert_eq!(reader.next_turn().unwrap().as_ref(), Some(&turns[2]));
+            assert!(reader.seek_to_turn(19).unwrap());
+            assert_eq!(reader.next_turn().unwrap().as_ref(), Some(&turns[19]));
+            assert_eq!(reader.next_turn().unwrap(), None);
+            assert!(!reader.seek_to_turn(20).unwrap());
+            assert!(reader.seek_to_turn(0).unwrap());
+            let mut count = 0;
+            while reader.next_turn().unwrap().is_some() {
+                count += 1;
+            }
+            assert_eq!(count, 20);
+        }
+    }
+}
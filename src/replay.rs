@@ -0,0 +1,105 @@
+use std::{io::{Read, Write}, fmt::{self, Display}};
+
+use rand::Rng;
+
+use crate::{AntJob, Turn, ai::turn, cli::Args, network::ProtocolError, recording::{RecordingReader, NO_ACTIONS}};
+
+/// Statistics about the differences between the recorded and the new actions of a replay.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Number of turns in the recording
+    turns: u64,
+    /// Number of turns in which at least one action differs
+    changed_turns: u64,
+    /// Number of actions that differ
+    changed_actions: u64,
+    /// Number of turns whose frame could not be decoded
+    skipped_turns: u64,
+    /// Number of turns that were not answered in the recorded game
+    unanswered_turns: u64,
+}
+
+impl Display for ReplayStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Replayed {} turns:", self.turns)?;
+        writeln!(f, " Turns with changed actions: {}", self.changed_turns)?;
+        writeln!(f, " Changed actions: {}", self.changed_actions)?;
+        writeln!(f, " Skipped turns: {}", self.skipped_turns)?;
+        write!(f, " Unanswered turns: {}", self.unanswered_turns)
+    }
+}
+
+/// Feeds every turn of the recording through the AI and writes the new actions to `sink`.
+///
+/// For each turn in which the new actions differ from the recorded ones the differences are printed.
+/// Turns are counted from 0. Turns that were not answered in the recorded game are left out, so that with a generator
+/// seeded like the one of the recorded game the AI makes the same random decisions.
+pub fn replay<R: Read, W: Write, G: Rng>(reader: &mut RecordingReader<R>, sink: &mut W, args: &Args, ant_jobs: &[AntJob], rng: &mut G) -> Result<ReplayStats, ProtocolError> {
+    let mut stats = ReplayStats::default();
+    while let Some(recorded) = reader.next_turn()? {
+        let nr = stats.turns;
+        stats.turns += 1;
+        if recorded.actions == NO_ACTIONS {
+            stats.unanswered_turns += 1;
+            continue;
+        }
+        let t = match Turn::from_bytes(&recorded.frame) {
+            Ok(t) => t,
+            Err(e) => {
+                println!("Turn {}: skipped: {}", nr, e);
+                stats.skipped_turns += 1;
+                continue;
+            }
+        };
+        let actions = turn(sink, &t, args, ant_jobs, rng);
+        let mut differences = Vec::new();
+        for (id, (recorded, new)) in recorded.actions.iter().zip(actions).enumerate() {
+            if *recorded != new {
+                differences.push(format!("ant {}: {} -> {}", id, recorded, new));
+            }
+        }
+        if !differences.is_empty() {
+            println!("Turn {}: {}", nr, differences.join(", "));
+            stats.changed_turns += 1;
+            stats.changed_actions += differences.len() as u64;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use clap::Parser;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{AntJob, Turn, Object, Pair, cli::Args, network::TeamName, recording::{Recorder, RecordingHeader, RecordedTurn, RecordingReader, NO_ACTIONS}};
+
+    use super::{replay, ReplayStats};
+
+    #[test]
+    fn test_replay() {
+        let jobs = [AntJob::Gatherer; 16];
+        let turn = Turn::test(0, vec![
+            Object { b1: Pair { upper: 1, lower: 0 }, b2: Pair { upper: 0, lower: 10 }, pos: (200, 200) },
+            Object { b1: Pair { upper: 2, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (210, 210) },
+        ]);
+        let path = env::temp_dir().join(format!("tondorf_ants_replay_{}.rec", process::id()));
+        let mut recorder = Recorder::create(&path, &RecordingHeader::new(TeamName::new("ants").unwrap(), &jobs, Some(1))).unwrap();
+        let mut actions = [5; 16];
+        recorder.record(&RecordedTurn { timestamp: 0, frame: turn.to_bytes(), actions }).unwrap();
+        actions[0] = 9;
+        recorder.record(&RecordedTurn { timestamp: 1, frame: turn.to_bytes(), actions }).unwrap();
+        recorder.record(&RecordedTurn { timestamp: 2, frame: vec![1, 2, 3], actions }).unwrap();
+        recorder.record(&RecordedTurn { timestamp: 3, frame: turn.to_bytes(), actions: NO_ACTIONS }).unwrap();
+
+        drop(recorder);
+        let mut reader = RecordingReader::open(&path).unwrap();
+        let mut sink = Vec::new();
+        let stats = replay(&mut reader, &mut sink, &Args::parse_from(["tondorf_ants", "-d"]), &jobs, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(stats, ReplayStats { turns: 4, changed_turns: 1, changed_actions: 1, skipped_turns: 1, unanswered_turns: 1 });
+        assert_eq!(sink, [actions, actions].concat());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+//! A scenario describes a fragment of the board as ASCII art, so that situations of a game can be written down by hand:
+//!
+//! ```text
+//! # Our gatherer carries sugar and is far away from its base
+//! us 0
+//! team 0 name=Rust_pirates points=120
+//! team 3 name=enemies points=40
+//! origin 95,120
+//! ant G team=0 id=0 job=gatherer cargo=sugar
+//! ant o team=0 id=1 health=3 job=offensive
+//! ant e team=3 id=7 health=2
+//! ant f team=3 id=8 at=400,400
+//! grid
+//! .....s....
+//! .....G.o..
+//! ..w.....e.
+//! ```
+//!
+//! Lines starting with `#` and empty lines are ignored. Every line before `grid` starts with a keyword:
+//! - `us <team>` sets the team id of the client, the default is 0.
+//! - `team <id> [name=<name>] [points=<points>]` sets the name and points of a team, teams without a line are named `ants` and have 0 points.
+//! - `origin <x>,<y>` sets the position of the upper left cell of the grid, the default is 0,0.
+//! - `ant <symbol> team=<team> id=<id> [health=<health>] [job=gatherer|offensive|waste_mover] [cargo=sugar|waste] [at=<x>,<y>]`
+//!   defines an ant, the health defaults to the health of a new ant. Ants of our team need a job, other ants must not have one.
+//!   Ants with `at` are placed at that position, all other ants are placed where their symbol is in the grid.
+//!
+//! Every line after `grid` is a row of the board, the row below has a y coordinate that is one larger.
+//! In the grid `.` is an empty cell, `s` is sugar, `w` is toxic waste and every other character is the symbol of an ant.
+//! The remaining ants of each team are the ants with health above 0.
+
+use std::{error::Error, fmt::{self, Display}, collections::{HashMap, HashSet}};
+
+use crate::{AntJob, AntCargo, Turn, Team, Object, ObjectKind, Pair, game::{BOARD_SIZE, MAX_HEALTH}, network::TeamName};
+
+/// A line of a scenario that is not valid.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScenarioError {
+    /// Number of the line, lines are counted from 1
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl Error for ScenarioError {}
+
+/// An ant as it is defined by an `ant` line
+#[derive(Debug)]
+struct AntLine {
+    team: u8,
+    id: u8,
+    health: u8,
+    job: Option<AntJob>,
+    cargo: Option<AntCargo>,
+    at: Option<(u16, u16)>,
+    /// Number of the line that defines the ant
+    line: usize,
+}
+
+/// A situation of a game that was loaded from the scenario format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    turn: Turn,
+    ant_jobs: Vec<AntJob>,
+}
+
+impl Scenario {
+
+    /// Parses a scenario.
+    ///
+    /// Fails with the number of the line when the scenario is not valid.
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        let mut team_id = 0;
+        let mut teams: Vec<Team> = (0..16).map(|id| Team { id, points: 0, remaining_ants: 0, team_name: TeamName::from_bytes(b"ants") }).collect();
+        let mut origin = (0, 0);
+        let mut ants: HashMap<char, AntLine> = HashMap::new();
+        let mut objects = Vec::new();
+        let mut placed = HashSet::new();
+        let mut row = None;
+        for (index, line) in text.lines().enumerate() {
+            let nr = index + 1;
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(y) = row {
+                for (x, symbol) in line.chars().enumerate() {
+                    let pos = position(origin, x, y).ok_or_else(|| error(nr, format!("cell {} is outside of the board", x + 1)))?;
+                    match symbol {
+                        '.' => (),
+                        's' => objects.push(object(ObjectKind::Sugar, 0, 0, 0, pos)),
+                        'w' => objects.push(object(ObjectKind::ToxicWaste, 0, 0, 0, pos)),
+                        _ => {
+                            let ant = ants.get(&symbol).ok_or_else(|| error(nr, format!("ant {} is not defined", symbol)))?;
+                            if ant.at.is_some() || !placed.insert(symbol) {
+                                return Err(error(nr, format!("ant {} is placed more than once", symbol)));
+                            }
+                            objects.push(object(ObjectKind::ant(ant.cargo.as_ref()), ant.team, ant.id, ant.health, pos));
+                        }
+                    }
+                }
+                row = Some(y + 1);
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            if keyword == "grid" {
+                row = Some(0);
+                continue;
+            }
+            let value = words.next().ok_or_else(|| error(nr, format!("{} needs a value", keyword)))?;
+            match keyword {
+                "us" => team_id = i16::from(number::<u8>(nr, "team", value, 15)?),
+                "team" => {
+                    let team = &mut teams[usize::from(number::<u8>(nr, "team", value, 15)?)];
+                    for (key, value) in words.map(|word| pair(nr, word)).collect::<Result<Vec<_>, _>>()? {
+                        match key {
+                            "name" => team.team_name = TeamName::new(value).map_err(|e| error(nr, e.to_string()))?,
+                            "points" => team.points = number(nr, "points", value, u16::MAX)?,
+                            _ => return Err(error(nr, format!("unknown key {}", key))),
+                        }
+                    }
+                }
+                "origin" => origin = coordinates(nr, value)?,
+                "ant" => {
+                    let mut chars = value.chars();
+                    let symbol = match (chars.next(), chars.next()) {
+                        (Some(symbol), None) if !matches!(symbol, '.' | 's' | 'w' | '#') => symbol,
+                        _ => return Err(error(nr, format!("{} can not be used as symbol of an ant", value))),
+                    };
+                    let mut ant = AntLine { team: 16, id: 16, health: MAX_HEALTH, job: None, cargo: None, at: None, line: nr };
+                    for (key, value) in words.map(|word| pair(nr, word)).collect::<Result<Vec<_>, _>>()? {
+                        match key {
+                            "team" => ant.team = number(nr, "team", value, 15)?,
+                            "id" => ant.id = number(nr, "ant id", value, 15)?,
+                            "health" => ant.health = number(nr, "health", value, MAX_HEALTH)?,
+                            "job" => ant.job = Some(match value {
+                                "gatherer" => AntJob::Gatherer,
+                                "offensive" => AntJob::Offensive,
+                                "waste_mover" => AntJob::WasteMover,
+                                _ => return Err(error(nr, format!("unknown job {}", value))),
+                            }),
+                            "cargo" => ant.cargo = Some(match value {
+                                "sugar" => AntCargo::Sugar,
+                                "waste" => AntCargo::ToxicWaste,
+                                _ => return Err(error(nr, format!("unknown cargo {}", value))),
+                            }),
+                            "at" => ant.at = Some(coordinates(nr, value)?),
+                            _ => return Err(error(nr, format!("unknown key {}", key))),
+                        }
+                    }
+                    if ant.team > 15 || ant.id > 15 {
+                        return Err(error(nr, String::from("ant needs a team and an id")));
+                    }
+                    if ants.values().any(|other| other.team == ant.team && other.id == ant.id) {
+                        return Err(error(nr, format!("team {} has more than one ant with id {}", ant.team, ant.id)));
+                    }
+                    if ants.insert(symbol, ant).is_some() {
+                        return Err(error(nr, format!("ant {} is defined more than once", symbol)));
+                    }
+                }
+                _ => return Err(error(nr, format!("unknown keyword {}", keyword))),
+            }
+        }
+        if row.is_none() {
+            return Err(error(text.lines().count(), String::from("grid is missing")));
+        }
+        let mut ant_jobs = vec![AntJob::Gatherer; 16];
+        let mut ants: Vec<(char, AntLine)> = ants.into_iter().collect();
+        ants.sort_by_key(|(_, ant)| ant.line);
+        for (symbol, ant) in ants {
+            match (i16::from(ant.team) == team_id, ant.job) {
+                (true, Some(job)) => ant_jobs[usize::from(ant.id)] = job,
+                (true, None) => return Err(error(ant.line, format!("ant {} of our team needs a job", symbol))),
+                (false, Some(_)) => return Err(error(ant.line, format!("ant {} of another team can not have a job", symbol))),
+                (false, None) => (),
+            }
+            match (ant.at, placed.contains(&symbol)) {
+                (Some(pos), _) => objects.push(object(ObjectKind::ant(ant.cargo.as_ref()), ant.team, ant.id, ant.health, pos)),
+                (None, true) => (),
+                (None, false) => return Err(error(ant.line, format!("ant {} is not placed on the grid", symbol))),
+            }
+            if ant.health > 0 {
+                teams[usize::from(ant.team)].remaining_ants += 1;
+            }
+        }
+        let turn = Turn { team_id, teams, _nr_of_objects: objects.len() as u16, objects };
+        Ok(Self { turn, ant_jobs })
+    }
+
+    /// The turn as the client would receive it in this situation
+    pub fn turn(&self) -> &Turn {
+        &self.turn
+    }
+
+    /// Jobs of the ants of our team, ants without a job in the scenario are gatherers.
+    pub fn ant_jobs(&self) -> &[AntJob] {
+        &self.ant_jobs
+    }
+}
+
+/// Builds the error for the line, lines are counted from 1.
+fn error(line: usize, reason: String) -> ScenarioError {
+    ScenarioError { line, reason }
+}
+
+fn object(kind: ObjectKind, team: u8, ant_id: u8, health: u8, pos: (u16, u16)) -> Object {
+    Object { b1: Pair { upper: kind.to_nibble(), lower: team }, b2: Pair { upper: ant_id, lower: health }, pos }
+}
+
+/// Returns the position of the cell in the grid or `None` if it is not on the board.
+fn position(origin: (u16, u16), column: usize, row: usize) -> Option<(u16, u16)> {
+    let x = usize::from(origin.0) + column;
+    let y = usize::from(origin.1) + row;
+    if x >= usize::from(BOARD_SIZE) || y >= usize::from(BOARD_SIZE) {
+        return None;
+    }
+    Some((x as u16, y as u16))
+}
+
+/// Parses a number that is at most `max`.
+fn number<T: TryFrom<u64> + Into<u64> + Copy>(line: usize, name: &str, value: &str, max: T) -> Result<T, ScenarioError> {
+    match value.parse::<u64>().ok().filter(|number| *number <= max.into()).and_then(|number| T::try_from(number).ok()) {
+        Some(number) => Ok(number),
+        None => Err(error(line, format!("{} {} is not a number between 0 and {}", name, value, max.into()))),
+    }
+}
+
+/// Parses coordinates written as `<x>,<y>`.
+fn coordinates(line: usize, value: &str) -> Result<(u16, u16), ScenarioError> {
+    let (x, y) = value.split_once(',').ok_or_else(|| error(line, format!("{} are not coordinates", value)))?;
+    Ok((number(line, "x", x, BOARD_SIZE - 1)?, number(line, "y", y, BOARD_SIZE - 1)?))
+}
+
+/// Splits a `<key>=<value>` pair.
+fn pair(line: usize, word: &str) -> Result<(&str, &str), ScenarioError> {
+    word.split_once('=').ok_or_else(|| error(line, format!("{} is not a key=value pair", word)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AntJob, Object, Pair, network::TeamName};
+
+    use super::Scenario;
+
+    #[test]
+    fn test_parse() {
+        let scenario = Scenario::parse("
+            # comment
+            us 3
+            team 3 name=Rust_pirates points=120
+            origin 10,20
+            ant A team=3 id=2 job=offensive cargo=sugar
+            ant b team=5 id=0 health=4 at=500,600
+            grid
+            s.
+            .A
+            w
+        ".lines().map(str::trim).collect::<Vec<_>>().join("\n").as_str()).unwrap();
+        let turn = scenario.turn();
+        assert_eq!(turn.team_id, 3);
+        assert_eq!(turn.teams[3].team_name, TeamName::new("Rust_pirates").unwrap());
+        assert_eq!(turn.teams[3].points, 120);
+        assert_eq!(turn.teams[3].remaining_ants, 1);
+        assert_eq!(turn.teams[5].remaining_ants, 1);
+        assert_eq!(turn.teams[0].points, 0);
+        assert_eq!(turn.objects, vec![
+            Object { b1: Pair { upper: 2, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (10, 20) },
+            Object { b1: Pair { upper: 3, lower: 3 }, b2: Pair { upper: 2, lower: 10 }, pos: (11, 21) },
+            Object { b1: Pair { upper: 4, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (10, 22) },
+            Object { b1: Pair { upper: 1, lower: 5 }, b2: Pair { upper: 0, lower: 4 }, pos: (500, 600) },
+        ]);
+        assert_eq!(turn._nr_of_objects, 4);
+        assert_eq!(scenario.ant_jobs()[2], AntJob::Offensive);
+        assert_eq!(scenario.ant_jobs()[0], AntJob::Gatherer);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (text, line) in [
+            ("origin 1,2", 1),
+            ("ant A team=0 id=0\ngrid\nA", 1),
+            ("ant A team=1 id=0 job=gatherer\ngrid\nA", 1),
+            ("ant A team=0 id=0 job=gatherer\ngrid\n.", 1),
+            ("ant A team=0 id=0 job=gatherer\ngrid\nAA", 3),
+            ("ant A team=0 id=0 job=gatherer\n\nant B team=0 id=0 job=gatherer\ngrid", 3),
+            ("ant A team=0 job=gatherer\ngrid", 1),
+            ("ant s team=0 id=0 job=gatherer\ngrid", 1),
+            ("ant A team=0 id=0 health=11 job=gatherer\ngrid", 1),
+            ("grid\n.B", 2),
+            ("origin 995,0\ngrid\n.....x", 3),
+            ("team 2 color=red\ngrid", 1),
+        ] {
+            let error = Scenario::parse(text).unwrap_err();
+            assert_eq!(error.line, line, "{}: {}", text, error);
+        }
+    }
+}
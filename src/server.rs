@@ -0,0 +1,160 @@
+use std::{io::{Read, Write}, net::{TcpListener, TcpStream, Shutdown}, sync::mpsc, thread, time::{Duration, Instant}, collections::HashSet, process::exit};
+
+use clap::Parser;
+
+use crate::{Turn, EXIT_SUCCESS, seed_or_random, EXIT_CONNECTION_FAILED, cli::ServerArgs, game::Game, network::{ProtocolError, Register}, summary::Scoreboard};
+
+/// Time a client has to send its registration after connecting
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A client that has registered at the server
+struct Client {
+    stream: TcpStream,
+    /// Id of the team the client controls, `None` for spectators
+    team_id: Option<usize>,
+}
+
+/// Runs the server with the command line arguments, this is the `main` of the `tondorf_server` binary.
+pub fn run_server() {
+    let args = ServerArgs::parse();
+    let listener = match TcpListener::bind(&args.listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("Unable to listen on {}: {}", args.listen, e);
+            exit(EXIT_CONNECTION_FAILED);
+        }
+    };
+    println!("Waiting for {} teams on {}", args.players, args.listen);
+    match serve(&listener, &args) {
+        Ok(last_turn) => {
+            println!("Game over!");
+            println!("{}", Scoreboard::new(&last_turn));
+            exit(EXIT_SUCCESS);
+        }
+        Err(e) => {
+            println!("Error: {}", e);
+            exit(EXIT_CONNECTION_FAILED);
+        }
+    }
+}
+
+/// Waits until enough teams have joined and plays the game with them.
+///
+/// Spectators that connect before the game starts receive the turns as well.
+/// Returns the last turn of the game as seen by team 0.
+fn serve(listener: &TcpListener, args: &ServerArgs) -> Result<Turn, ProtocolError> {
+    let mut game = Game::new(seed_or_random(args.seed));
+    let mut clients = Vec::new();
+    let (sender, receiver) = mpsc::channel();
+    let mut players = 0;
+    while players < args.players {
+        let (mut stream, address) = listener.accept()?;
+        stream.set_read_timeout(Some(REGISTER_TIMEOUT))?;
+        let mut registration = [0u8; 18];
+        if let Err(e) = stream.read_exact(&mut registration) {
+            println!("Client {} did not register: {}", address, e);
+            continue;
+        }
+        stream.set_read_timeout(None)?;
+        stream.set_nodelay(true)?;
+        let register = Register::from_bytes(&registration);
+        if register.is_spectator() {
+            println!("Spectator {} connected from {}", register.team_name(), address);
+            clients.push(Client { stream, team_id: None });
+            continue;
+        }
+        // There are at most 16 players, so a slot is always free
+        let team_id = game.join(register.team_name().clone()).expect("all team slots are taken");
+        println!("Team {} connected from {} and plays as team {}", register.team_name(), address, team_id);
+        players += 1;
+        let mut input = stream.try_clone()?;
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let mut actions = [0u8; 16];
+            while input.read_exact(&mut actions).is_ok() {
+                if sender.send((team_id, actions)).is_err() {
+                    break;
+                }
+            }
+        });
+        clients.push(Client { stream, team_id: Some(team_id) });
+    }
+    println!("Starting the game");
+    let tick = Duration::from_millis(args.tick);
+    for _ in 0..args.turns {
+        clients.retain_mut(|client| {
+            let frame = game.turn(client.team_id.unwrap_or(0)).to_bytes();
+            match client.stream.write_all(&frame) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("Connection to team {:?} lost: {}", client.team_id, e);
+                    false
+                }
+            }
+        });
+        let playing = clients.iter().filter(|client| client.team_id.is_some()).count();
+        let mut actions = [[0u8; 16]; 16];
+        let mut answered = HashSet::new();
+        let deadline = Instant::now() + tick;
+        while answered.len() < playing {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok((team_id, team_actions)) => {
+                    actions[team_id] = team_actions;
+                    answered.insert(team_id);
+                }
+                Err(_) => break,
+            }
+        }
+        game.step(&actions);
+    }
+    for client in &clients {
+        // The client sees the closed connection as end of the game
+        let _ = client.stream.shutdown(Shutdown::Both);
+    }
+    Ok(game.turn(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{Read, Write}, net::{TcpListener, TcpStream}, thread};
+
+    use clap::Parser;
+
+    use crate::{cli::ServerArgs, network::{read_frame, TeamName}, Turn};
+
+    use super::serve;
+
+    #[test]
+    fn test_serve() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let args = ServerArgs::parse_from(["tondorf_server", "--players", "1", "--turns", "3", "--tick", "5000"]);
+            serve(&listener, &args).unwrap()
+        });
+
+        let mut spectator = TcpStream::connect(address).unwrap();
+        spectator.write_all(b"\0\0watcher\0\0\0\0\0\0\0\0\0").unwrap();
+        let mut player = TcpStream::connect(address).unwrap();
+        player.write_all(b"\x01\0Rust_pirates\0\0\0\0").unwrap();
+        for _ in 0..3 {
+            let turn = Turn::from_bytes(&read_frame(&mut player).unwrap().unwrap()).unwrap();
+            assert_eq!(turn.team_id, 0);
+            assert_eq!(turn.teams[0].team_name, TeamName::new("Rust_pirates").unwrap());
+            assert_eq!(turn.teams[0].remaining_ants, 16);
+            assert_eq!(turn.teams[1].remaining_ants, 0);
+            player.write_all(&[5; 16]).unwrap();
+        }
+        let mut rest = Vec::new();
+        player.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+        let last_turn = server.join().unwrap();
+        assert_eq!(last_turn.teams[0].team_name, TeamName::new("Rust_pirates").unwrap());
+
+        let mut frames = 0;
+        while read_frame(&mut spectator).unwrap().is_some() {
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+    }
+}
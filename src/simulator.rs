@@ -0,0 +1,156 @@
+use std::{io, error::Error, fmt::{self, Display}};
+
+use rand::rngs::StdRng;
+
+use crate::{AntJob, Turn, ai_rng, ai::turn, cli::Args, game::Game, network::TeamName};
+
+/// Errors that can occur while setting up a simulated game.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SimulatorError {
+    /// There are more teams than bases.
+    TooManyTeams(usize),
+    /// A team id is larger than 15.
+    TeamIdOutOfRange(usize),
+    /// A team id is used by more than one team.
+    DuplicateTeamId(usize),
+    /// A team name can not be sent to the clients.
+    InvalidTeamName { name: String, reason: String },
+}
+
+impl Display for SimulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulatorError::TooManyTeams(teams) => write!(f, "{} teams can not play on 16 bases", teams),
+            SimulatorError::TeamIdOutOfRange(id) => write!(f, "team id {} is not between 0 and 15", id),
+            SimulatorError::DuplicateTeamId(id) => write!(f, "team id {} is used more than once", id),
+            SimulatorError::InvalidTeamName { name, reason } => write!(f, "invalid team name {}: {}", name, reason),
+        }
+    }
+}
+
+impl Error for SimulatorError {}
+
+/// A game that runs in the same process without any sockets.
+///
+/// Turns are built directly from the game state and the actions are applied without encoding them,
+/// so that the AI can be driven through thousands of turns per second.
+#[derive(Debug)]
+pub struct Simulator {
+    game: Game,
+    /// Generator for the random decisions of the AI
+    rng: StdRng,
+    turns_played: u64,
+}
+
+impl Simulator {
+
+    /// Creates a game in which the teams join in the given order, the first team gets the id 0.
+    ///
+    /// The placement of objects and the random decisions of the AI are derived from `seed`, so that simulations with the same seed
+    /// and the same players have the same result. The AI draws from a separate generator, like it does in the client.
+    /// Fails when a team name is not valid or when there are more than 16 teams.
+    pub fn new(team_names: &[&str], seed: u64) -> Result<Self, SimulatorError> {
+        if team_names.len() > 16 {
+            return Err(SimulatorError::TooManyTeams(team_names.len()));
+        }
+        let teams: Vec<(usize, &str)> = team_names.iter().copied().enumerate().collect();
+        Self::with_team_ids(&teams, seed)
+    }
+
+    /// Creates a game in which each team plays with the given id and therefore from the base with that id.
+    ///
+    /// Fails when a team name is not valid or when an id is larger than 15 or used more than once.
+    pub fn with_team_ids(teams: &[(usize, &str)], seed: u64) -> Result<Self, SimulatorError> {
+        let mut game = Game::new(seed);
+        for (id, name) in teams {
+            if *id > 15 {
+                return Err(SimulatorError::TeamIdOutOfRange(*id));
+            }
+            let team_name = TeamName::new(name).map_err(|e| SimulatorError::InvalidTeamName { name: name.to_string(), reason: e.to_string() })?;
+            if !game.join_at(*id, team_name) {
+                return Err(SimulatorError::DuplicateTeamId(*id));
+            }
+        }
+        Ok(Self { game, rng: ai_rng(seed), turns_played: 0 })
+    }
+
+    /// Number of turns that have been played
+    pub fn turns_played(&self) -> u64 {
+        self.turns_played
+    }
+
+    /// Builds the turn as the team with the id would receive it.
+    pub fn turn(&self, team_id: usize) -> Turn {
+        self.game.turn(team_id)
+    }
+
+    /// Plays one turn with the actions of all teams, actions are indexed by team id and ant id.
+    pub fn step(&mut self, actions: &[[u8; 16]; 16]) {
+        self.game.step(actions);
+        self.turns_played += 1;
+    }
+
+    /// Lets the AI calculate the actions of the teams and plays one turn with them.
+    ///
+    /// `players` contains the team id, arguments and ant jobs of each team the AI plays for, the ants of all other teams stay where they are.
+    /// Returns the actions that were played.
+    pub fn step_ai(&mut self, players: &[(usize, &Args, &[AntJob])]) -> [[u8; 16]; 16] {
+        let mut actions = [[0u8; 16]; 16];
+        for (team_id, args, ant_jobs) in players {
+            actions[*team_id] = turn(&mut io::sink(), &self.game.turn(*team_id), args, ant_jobs, &mut self.rng);
+        }
+        self.step(&actions);
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::{AntJob, cli::Args};
+
+    use super::{Simulator, SimulatorError};
+
+    #[test]
+    fn test_simulator() {
+        assert!(matches!(Simulator::new(&["a_very_long_team_name"], 1), Err(SimulatorError::InvalidTeamName { .. })));
+        assert_eq!(Simulator::new(&["ants"; 17], 1).unwrap_err(), SimulatorError::TooManyTeams(17));
+        assert_eq!(Simulator::with_team_ids(&[(16, "ants")], 1).unwrap_err(), SimulatorError::TeamIdOutOfRange(16));
+        assert_eq!(Simulator::with_team_ids(&[(3, "ants"), (3, "more_ants")], 1).unwrap_err(), SimulatorError::DuplicateTeamId(3));
+        let mut simulator = Simulator::with_team_ids(&[(7, "ants")], 1).unwrap();
+        assert_eq!(simulator.turn(7).teams[7].remaining_ants, 16);
+        assert_eq!(simulator.turn(7).teams[0].remaining_ants, 0);
+        let args = Args::parse_from(["tondorf_ants", "-d"]);
+        let actions = simulator.step_ai(&[(7, &args, &[AntJob::Gatherer; 16])]);
+        assert!(actions[7].iter().all(|action| *action != 0));
+        assert_eq!(actions[0], [0; 16]);
+
+        let mut simulator = Simulator::new(&["gatherers", "fighters"], 1).unwrap();
+        let turn = simulator.turn(1);
+        assert_eq!(turn.team_id, 1);
+        assert_eq!(turn.teams[1].remaining_ants, 16);
+        assert_eq!(turn.teams[2].remaining_ants, 0);
+
+        let gatherers = Args::parse_from(["tondorf_ants", "-g", "16", "-o", "0", "-w", "0"]);
+        let fighters = Args::parse_from(["tondorf_ants", "-g", "0", "-o", "16", "-w", "0"]);
+        let players: [(usize, &Args, &[AntJob]); 2] = [(0, &gatherers, &[AntJob::Gatherer; 16]), (1, &fighters, &[AntJob::Offensive; 16])];
+        let mut moved = false;
+        for _ in 0..1000 {
+            let actions = simulator.step_ai(&players);
+            moved |= actions[0].iter().any(|action| *action != 5);
+            assert_eq!(actions[2], [0; 16]);
+        }
+        assert!(moved);
+        assert_eq!(simulator.turns_played(), 1000);
+        let turn = simulator.turn(0);
+        assert!(turn.teams[0].points + turn.teams[1].points > 0);
+
+        // The same seed leads to the same game
+        let mut again = Simulator::new(&["gatherers", "fighters"], 1).unwrap();
+        for _ in 0..1000 {
+            again.step_ai(&players);
+        }
+        assert_eq!(again.turn(0), turn);
+    }
+}
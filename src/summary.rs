@@ -0,0 +1,114 @@
+use std::fmt::{self, Display};
+
+use crate::{Turn, network::TeamName};
+
+/// Result of a game as seen from the last turn that was received.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GameSummary {
+    /// Name of the own team
+    team_name: TeamName,
+    /// Points of the own team
+    points: u16,
+    /// Place of the own team, 1 is the best, teams with equal points share a place
+    rank: usize,
+    /// Number of turns that were played
+    turns_played: u64,
+    /// Number of own ants that are still alive
+    surviving_ants: usize,
+}
+
+impl GameSummary {
+    /// Creates the summary from the last turn of the game.
+    pub fn new(last_turn: &Turn, turns_played: u64) -> Self {
+        let own_team = &last_turn.teams[last_turn.team_id as usize];
+        let rank = 1 + last_turn.teams.iter().filter(|team| team.points > own_team.points).count();
+        let surviving_ants = last_turn.objects.iter()
+            .filter(|object| object.kind().is_ant() && i16::from(object.b1.lower) == last_turn.team_id)
+            .count();
+        Self {
+            team_name: own_team.team_name.clone(),
+            points: own_team.points,
+            rank,
+            turns_played,
+            surviving_ants,
+        }
+    }
+}
+
+impl Display for GameSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Game over!")?;
+        writeln!(f, " Team: {}", self.team_name)?;
+        writeln!(f, " Points: {}", self.points)?;
+        writeln!(f, " Rank: {}/16", self.rank)?;
+        writeln!(f, " Turns played: {}", self.turns_played)?;
+        write!(f, " Surviving ants: {}", self.surviving_ants)
+    }
+}
+
+/// Points and remaining ants of all teams, ordered by points.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Scoreboard {
+    /// Place, name, points and remaining ants of each team, teams with equal points share a place
+    entries: Vec<(usize, TeamName, u16, u16)>,
+}
+
+impl Scoreboard {
+    /// Creates the scoreboard from the teams of the turn.
+    pub fn new(turn: &Turn) -> Self {
+        let mut teams: Vec<_> = turn.teams.iter().collect();
+        teams.sort_by(|a, b| b.points.cmp(&a.points).then(a.id.cmp(&b.id)));
+        let entries = teams.iter()
+            .map(|team| {
+                let rank = 1 + teams.iter().filter(|other| other.points > team.points).count();
+                (rank, team.team_name.clone(), team.points, team.remaining_ants)
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+impl Display for Scoreboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Scoreboard:")?;
+        for (rank, team_name, points, remaining_ants) in &self.entries {
+            write!(f, "\n {:>2}. {:<16} {:>5} points {:>2} ants", rank, team_name.to_string(), points, remaining_ants)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Turn, Object, Pair};
+
+    use super::{GameSummary, Scoreboard};
+
+    #[test]
+    fn test_game_summary() {
+        let turn = Turn::test(4, vec![
+            Object { b1: Pair { upper: 1, lower: 4 }, b2: Pair { upper: 0, lower: 10 }, pos: (1, 1) },
+            Object { b1: Pair { upper: 3, lower: 4 }, b2: Pair { upper: 1, lower: 10 }, pos: (2, 2) },
+            Object { b1: Pair { upper: 1, lower: 5 }, b2: Pair { upper: 0, lower: 10 }, pos: (3, 3) },
+        ]).with_teams(|team| team.points = [5, 20, 10][team.id as usize % 3]);
+        let summary = GameSummary::new(&turn, 250);
+        assert_eq!(summary.points, 20);
+        assert_eq!(summary.rank, 1);
+        assert_eq!(summary.surviving_ants, 2);
+        assert_eq!(summary.turns_played, 250);
+        let turn = Turn { team_id: 0, ..turn };
+        assert_eq!(GameSummary::new(&turn, 250).rank, 11);
+    }
+
+    #[test]
+    fn test_scoreboard() {
+        let turn = Turn::test(0, Vec::new()).with_teams(|team| team.points = [5, 20, 10][team.id as usize % 3]);
+        let scoreboard = Scoreboard::new(&turn).to_string();
+        let lines: Vec<&str> = scoreboard.lines().collect();
+        assert_eq!(lines.len(), 17);
+        assert_eq!(lines[0], "Scoreboard:");
+        assert_eq!(lines[1], "  1. ants                20 points 16 ants");
+        assert_eq!(lines[6], "  6. ants                10 points 16 ants");
+        assert_eq!(lines[16], " 11. ants                 5 points 16 ants");
+    }
+}
@@ -0,0 +1,182 @@
+use std::{io, fmt::{self, Display}, sync::mpsc::{self, Sender, Receiver, RecvTimeoutError}, thread, time::{Duration, Instant}};
+
+use rand::rngs::StdRng;
+
+use crate::{AntJob, Turn, ai::turn, cli::Args};
+
+/// Action that lets an ant stay where it is
+const STAY: u8 = 5;
+
+/// Number, sum and extremes of measured durations.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DurationStats {
+    count: u32,
+    total: Duration,
+    min: Option<Duration>,
+    max: Duration,
+}
+
+impl DurationStats {
+
+    /// Adds a measured duration.
+    pub fn add(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.min = Some(self.min.map_or(duration, |min| min.min(duration)));
+        self.max = self.max.max(duration);
+    }
+}
+
+impl Display for DurationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.min {
+            Some(min) => write!(f, "avg {:.1}ms, min {:.1}ms, max {:.1}ms",
+                (self.total / self.count).as_secs_f64() * 1000.0, min.as_secs_f64() * 1000.0, self.max.as_secs_f64() * 1000.0),
+            None => write!(f, "not measured"),
+        }
+    }
+}
+
+/// Time between the frames of the server and time the AI needed to answer them.
+#[derive(Debug, Default)]
+pub struct TimingStats {
+    /// Time between two consecutive frames of the same connection
+    pub intervals: DurationStats,
+    /// Time from receiving a frame until the actions were ready
+    pub compute: DurationStats,
+    /// Number of turns in which the fallback actions were sent because the AI overran its budget
+    pub overruns: u64,
+    /// Number of frames that were not answered because a newer frame was already received
+    pub skipped_frames: u64,
+}
+
+impl Display for TimingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Timing:")?;
+        writeln!(f, " Time between turns: {}", self.intervals)?;
+        writeln!(f, " AI compute time: {}", self.compute)?;
+        writeln!(f, " Turns over budget: {}", self.overruns)?;
+        write!(f, " Frames skipped to catch up: {}", self.skipped_frames)
+    }
+}
+
+/// Runs the AI on its own thread so that actions can be sent before the server's cutoff even if the AI is too slow.
+///
+/// When the AI does not answer within the budget the fallback actions are used: each ant repeats the last action
+/// the AI calculated for it in time, ants without such an action stay where they are.
+/// Turns that are still waiting when the AI gets to them are skipped in favor of the newest one.
+/// Which turns the AI answers and draws random decisions for depends on timing, so a game played with a watchdog
+/// can not be replayed with the same decisions and its recording does not contain the seed.
+#[derive(Debug)]
+pub struct Watchdog {
+    budget: Duration,
+    requests: Sender<(u64, Turn)>,
+    results: Receiver<(u64, [u8; 16])>,
+    /// Number of the next turn that is sent to the AI
+    next: u64,
+    fallback: [u8; 16],
+}
+
+impl Watchdog {
+
+    /// Starts the AI thread that answers turns with the arguments and ant jobs, random decisions are drawn from `rng`.
+    pub fn spawn(budget: Duration, args: Args, ant_jobs: Vec<AntJob>, mut rng: StdRng) -> Self {
+        let (requests, pending) = mpsc::channel::<(u64, Turn)>();
+        let (answers, results) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut request) = pending.recv() {
+                while let Ok(newer) = pending.try_recv() {
+                    request = newer;
+                }
+                let (nr, t) = request;
+                let actions = turn(&mut io::sink(), &t, &args, &ant_jobs, &mut rng);
+                if answers.send((nr, actions)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { budget, requests, results, next: 0, fallback: [STAY; 16] }
+    }
+
+    /// Calculates the actions for the turn.
+    ///
+    /// Returns the fallback actions and `true` when the AI did not answer within the budget.
+    pub fn actions(&mut self, t: Turn) -> ([u8; 16], bool) {
+        let deadline = Instant::now() + self.budget;
+        let nr = self.next;
+        self.next += 1;
+        if self.requests.send((nr, t)).is_err() {
+            // The AI thread panicked
+            return (self.fallback, true);
+        }
+        loop {
+            match self.results.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok((answered, actions)) if answered == nr => {
+                    self.fallback = actions;
+                    return (actions, false);
+                }
+                // Answer to a turn that already got the fallback actions
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return (self.fallback, true),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, thread, time::Duration};
+
+    use clap::Parser;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{AntJob, Turn, Object, Pair, ai::turn, cli::Args};
+
+    use super::{DurationStats, Watchdog, STAY};
+
+    #[test]
+    fn test_duration_stats() {
+        let mut stats = DurationStats::default();
+        assert_eq!(stats.to_string(), "not measured");
+        for ms in [100, 50, 150] {
+            stats.add(Duration::from_millis(ms));
+        }
+        assert_eq!(stats.to_string(), "avg 100.0ms, min 50.0ms, max 150.0ms");
+    }
+
+    #[test]
+    fn test_watchdog() {
+        let t = Turn::test(0, vec![
+            Object { b1: Pair { upper: 1, lower: 0 }, b2: Pair { upper: 3, lower: 10 }, pos: (200, 200) },
+            Object { b1: Pair { upper: 2, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (210, 210) },
+        ]);
+        let args = Args::parse_from(["tondorf_ants", "-d"]);
+        let jobs = vec![AntJob::Gatherer; 16];
+        let expected = turn(&mut io::sink(), &t, &args, &jobs, &mut StdRng::seed_from_u64(1));
+        let mut watchdog = Watchdog::spawn(Duration::from_secs(10), args, jobs, StdRng::seed_from_u64(1));
+        assert_eq!(watchdog.actions(t.clone()), (expected, false));
+        assert_eq!(watchdog.fallback, expected);
+        assert_eq!(watchdog.actions(t), (expected, false));
+    }
+
+    #[test]
+    fn test_watchdog_overrun() {
+        let sugar = Object { b1: Pair { upper: 2, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (210, 210) };
+        let first = Turn::test(0, vec![Object { b1: Pair { upper: 1, lower: 0 }, b2: Pair { upper: 3, lower: 10 }, pos: (200, 200) }, sugar.clone()]);
+        let second = Turn::test(0, vec![Object { b1: Pair { upper: 1, lower: 0 }, b2: Pair { upper: 3, lower: 10 }, pos: (220, 200) }, sugar]);
+        let args = Args::parse_from(["tondorf_ants", "-d"]);
+        let jobs = vec![AntJob::Gatherer; 16];
+        let mut rng = StdRng::seed_from_u64(1);
+        let late = turn(&mut io::sink(), &first, &args, &jobs, &mut rng);
+        let expected = turn(&mut io::sink(), &second, &args, &jobs, &mut rng);
+        assert_ne!(late, expected);
+
+        let mut watchdog = Watchdog::spawn(Duration::ZERO, args, jobs, StdRng::seed_from_u64(1));
+        assert_eq!(watchdog.actions(first), ([STAY; 16], true));
+        // Give the AI time to send its late answer
+        thread::sleep(Duration::from_millis(100));
+        watchdog.budget = Duration::from_secs(10);
+        assert_eq!(watchdog.actions(second), (expected, false));
+        assert_eq!(watchdog.fallback, expected);
+    }
+}
@@ -0,0 +1,315 @@
+//! A tournament plays games between variants of the client on the simulator.
+//!
+//! Variants are read from a file in which every line contains the name of a variant and its client arguments:
+//!
+//! ```text
+//! # name     arguments
+//! default    -d
+//! hunters    -g 10 -o 6 -w 0 --hunt
+//! random     -r
+//! ```
+//!
+//! Lines starting with `#` and empty lines are ignored. Each game is played by randomly chosen variants from randomly chosen bases.
+//! For the ratings every game is split into games between each two of its variants, the variant with more points wins.
+
+use std::{io::{self, Write}, error::Error, fmt::{self, Display}};
+
+use clap::Parser;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::{AntJob, set_ant_jobs, cli::Args, network::TeamName, simulator::{Simulator, SimulatorError}};
+
+/// Z value of the 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Errors that can occur while reading the variants or playing the tournament.
+#[derive(Debug)]
+pub enum TournamentError {
+    /// A line of the variants file is not valid, lines are counted from 1.
+    Variant { line: usize, reason: String },
+    /// There are fewer variants than teams in a game.
+    TooFewVariants { variants: usize, players: usize },
+    /// A game could not be set up.
+    Simulator(SimulatorError),
+    /// Reading the variants or writing the results failed.
+    Io(io::Error),
+}
+
+impl Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TournamentError::Variant { line, reason } => write!(f, "line {}: {}", line, reason),
+            TournamentError::TooFewVariants { variants, players } => write!(f, "{} variants are needed for games with {} teams, {} are defined", players, players, variants),
+            TournamentError::Simulator(e) => write!(f, "unable to set up game: {}", e),
+            TournamentError::Io(e) => write!(f, "i/o error: {}", e),
+        }
+    }
+}
+
+impl Error for TournamentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TournamentError::Simulator(e) => Some(e),
+            TournamentError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TournamentError {
+    fn from(e: io::Error) -> Self {
+        TournamentError::Io(e)
+    }
+}
+
+impl From<SimulatorError> for TournamentError {
+    fn from(e: SimulatorError) -> Self {
+        TournamentError::Simulator(e)
+    }
+}
+
+/// A configured variant of the client
+#[derive(Debug)]
+pub struct Variant {
+    name: String,
+    args: Args,
+}
+
+/// Result of a variant in one game, written as part of a JSON line
+#[derive(Debug, Serialize)]
+struct TeamResult<'a> {
+    variant: &'a str,
+    team_id: usize,
+    points: u16,
+    remaining_ants: u16,
+}
+
+/// Result of one game, written as JSON line
+#[derive(Debug, Serialize)]
+struct GameResult<'a> {
+    game: u32,
+    seed: u64,
+    teams: Vec<TeamResult<'a>>,
+}
+
+/// Games of a variant against single other variants
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Record {
+    name: String,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl Record {
+
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Share of the possible points the variant got, a draw counts as half a win
+    fn score(&self) -> f64 {
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(self.games())
+    }
+
+    /// Elo difference to the average opponent and the margin of the 95% confidence interval
+    fn elo(&self) -> (f64, f64) {
+        let games = f64::from(self.games());
+        let score = self.score();
+        let variance = (f64::from(self.wins) * (1.0 - score).powi(2) + f64::from(self.draws) * (0.5 - score).powi(2)
+            + f64::from(self.losses) * score.powi(2)) / games;
+        let deviation = CONFIDENCE_Z * (variance / games).sqrt();
+        let upper = elo((score + deviation).min(1.0));
+        let lower = elo((score - deviation).max(0.0));
+        let rating = elo(score);
+        // Without a single win or loss the rating and at least one bound of the interval are infinite
+        let margin = if rating.is_finite() { (upper - lower) / 2.0 } else { f64::INFINITY };
+        (rating, margin)
+    }
+}
+
+/// Ratings of the variants after a tournament
+#[derive(Debug, PartialEq, Eq)]
+pub struct Standings {
+    games: u32,
+    players: usize,
+    records: Vec<Record>,
+}
+
+impl Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Standings after {} games with {} teams each:", self.games, self.players)?;
+        write!(f, " Rank Variant          Games  Wins Draws Losses  Score       Elo")?;
+        let mut records: Vec<&Record> = self.records.iter().collect();
+        records.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        for (rank, record) in records.iter().enumerate() {
+            write!(f, "\n {:>3}. {:<16} {:>5} {:>5} {:>5} {:>6}", rank + 1, record.name, record.games(), record.wins, record.draws, record.losses)?;
+            if record.games() == 0 {
+                write!(f, "      -         -")?;
+                continue;
+            }
+            let (elo, margin) = record.elo();
+            write!(f, " {:>5.1}% {:>+5.0} ± {:.0}", record.score() * 100.0, elo, margin)?;
+        }
+        write!(f, "\nElo is relative to the average opponent, ± is the 95% confidence interval.")
+    }
+}
+
+/// Elo difference that corresponds to the score, infinite for a score of 0 or 1
+fn elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Parses the variants file.
+///
+/// Fails with the number of the line when a name or the arguments are not valid.
+pub fn parse_variants(text: &str) -> Result<Vec<Variant>, TournamentError> {
+    let mut variants: Vec<Variant> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |reason: String| TournamentError::Variant { line: index + 1, reason };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        TeamName::new(name).map_err(|e| error(e.to_string()))?;
+        if variants.iter().any(|variant| variant.name == name) {
+            return Err(error(format!("variant {} is defined more than once", name)));
+        }
+        let args = Args::try_parse_from(["tondorf_ants"].into_iter().chain(words))
+            .map_err(|e| error(e.render().to_string().lines().next().unwrap_or_default().to_string()))?;
+        if args.spectate || args.command.is_some() {
+            return Err(error(String::from("a variant has to play and can not run a command")));
+        }
+        if !args.default_jobs && !args.random_jobs {
+            let total = u32::from(args.gatherer_ants.unwrap_or(0)) + u32::from(args.offensive_ants.unwrap_or(0)) + u32::from(args.waste_mover_ants.unwrap_or(0));
+            if total != 16 {
+                return Err(error(format!("total job count != 16, was {}", total)));
+            }
+        }
+        variants.push(Variant { name: name.to_string(), args });
+    }
+    Ok(variants)
+}
+
+/// Plays the games of the tournament and writes the result of each game as JSON line to `results`.
+///
+/// `players` variants take part in each game, which are played for `turns` turns.
+/// The choice of variants, bases and random jobs as well as the games themselves are derived from `seed`.
+pub fn tournament<W: Write>(variants: &[Variant], games: u32, turns: u64, players: usize, seed: u64, results: &mut W) -> Result<Standings, TournamentError> {
+    if variants.len() < players {
+        return Err(TournamentError::TooFewVariants { variants: variants.len(), players });
+    }
+    let mut records: Vec<Record> = variants.iter().map(|variant| Record { name: variant.name.clone(), ..Record::default() }).collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    for game in 0..games {
+        let mut chosen: Vec<usize> = (0..variants.len()).collect();
+        chosen.shuffle(&mut rng);
+        chosen.truncate(players);
+        let mut bases: Vec<usize> = (0..16).collect();
+        bases.shuffle(&mut rng);
+        let game_seed = rng.gen();
+        let teams: Vec<(usize, &str)> = chosen.iter().zip(&bases).map(|(variant, id)| (*id, variants[*variant].name.as_str())).collect();
+        let mut simulator = Simulator::with_team_ids(&teams, game_seed)?;
+        let jobs: Vec<Vec<AntJob>> = chosen.iter().map(|variant| set_ant_jobs(&variants[*variant].args, &mut rng)).collect();
+        let lineup: Vec<(usize, &Args, &[AntJob])> = chosen.iter().zip(&bases).zip(&jobs)
+            .map(|((variant, id), ant_jobs)| (*id, &variants[*variant].args, ant_jobs.as_slice())).collect();
+        for _ in 0..turns {
+            simulator.step_ai(&lineup);
+        }
+        let last_turn = simulator.turn(0);
+        let result = GameResult {
+            game,
+            seed: game_seed,
+            teams: chosen.iter().zip(&bases).map(|(variant, id)| TeamResult {
+                variant: &variants[*variant].name,
+                team_id: *id,
+                points: last_turn.teams[*id].points,
+                remaining_ants: last_turn.teams[*id].remaining_ants,
+            }).collect(),
+        };
+        serde_json::to_writer(&mut *results, &result).map_err(io::Error::from)?;
+        writeln!(results)?;
+        let summary: Vec<String> = result.teams.iter().map(|team| format!("{} {}", team.variant, team.points)).collect();
+        println!("Game {}: {}", game + 1, summary.join(", "));
+        for (a, first) in chosen.iter().zip(&result.teams) {
+            for (b, second) in chosen.iter().zip(&result.teams) {
+                if a == b {
+                    continue;
+                }
+                match first.points.cmp(&second.points) {
+                    std::cmp::Ordering::Greater => records[*a].wins += 1,
+                    std::cmp::Ordering::Equal => records[*a].draws += 1,
+                    std::cmp::Ordering::Less => records[*a].losses += 1,
+                }
+            }
+        }
+    }
+    Ok(Standings { games, players, records })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{elo, parse_variants, tournament, Record, TournamentError};
+
+    #[test]
+    fn test_parse_variants() {
+        let variants = parse_variants("# name arguments\n\ndefault -d\nhunters -g 10 -o 6 -w 0 --hunt\n").unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[1].name, "hunters");
+        assert!(variants[1].args.hunt);
+        assert_eq!(variants[1].args.gatherer_ants, Some(10));
+
+        for (text, line) in [
+            ("default -d\ndefault -r", 2),
+            ("a_very_long_variant_name -d", 1),
+            ("default --unknown", 1),
+            ("few -g 1 -o 1 -w 1", 1),
+            ("watcher --spectate", 1),
+            ("\nnothing", 2),
+        ] {
+            match parse_variants(text) {
+                Err(TournamentError::Variant { line: error_line, reason }) => assert_eq!(error_line, line, "{}: {}", text, reason),
+                result => panic!("{}: {:?}", text, result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_elo() {
+        assert_eq!(elo(0.5), 0.0);
+        assert!((elo(0.75) - 190.8).abs() < 0.1);
+        assert_eq!(elo(1.0), f64::INFINITY);
+        assert_eq!(elo(0.0), f64::NEG_INFINITY);
+        let record = Record { name: String::from("ants"), wins: 60, draws: 20, losses: 20 };
+        assert_eq!(record.score(), 0.7);
+        let (rating, margin) = record.elo();
+        assert!((rating - 147.2).abs() < 0.1);
+        assert!(margin > 0.0 && margin < 100.0);
+        assert_eq!(Record { name: String::from("ants"), wins: 0, draws: 0, losses: 6 }.elo(), (f64::NEG_INFINITY, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_tournament() {
+        let variants = parse_variants("default -d\ngatherers -g 16 -o 0 -w 0\nrandom -r").unwrap();
+        assert!(matches!(tournament(&variants, 1, 1, 4, 1, &mut io::sink()), Err(TournamentError::TooFewVariants { variants: 3, players: 4 })));
+
+        let mut results = Vec::new();
+        let standings = tournament(&variants, 4, 50, 2, 7, &mut results).unwrap();
+        assert_eq!(standings.records.iter().map(Record::games).sum::<u32>(), 8);
+        let lines: Vec<serde_json::Value> = String::from_utf8(results.clone()).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3]["game"], 3);
+        assert_eq!(lines[0]["teams"].as_array().unwrap().len(), 2);
+        assert!(standings.to_string().starts_with("Standings after 4 games with 2 teams each:\n Rank Variant"));
+
+        // The same seed leads to the same tournament
+        let mut again = Vec::new();
+        assert_eq!(tournament(&variants, 4, 50, 2, 7, &mut again).unwrap(), standings);
+        assert_eq!(again, results);
+    }
+}
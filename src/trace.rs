@@ -0,0 +1,149 @@
+//! Every line of a trace starts with the offset of the bytes inside of the frame, followed by the bytes in hex
+//! and the values that were decoded from them:
+//!
+//! ```text
+//! <<< frame 0 at 1676000000000, 330 bytes
+//! 0000  03 00                      team id: 3 (big endian: 768)
+//! 0002  05 00 10 00 52 75 73 74 …  team 0: points 5, remaining ants 16, name "Rust_pirates"
+//! 0142  01 00                      number of objects: 1
+//! 0144  13 a7 64 00 78 00          object 0: type 1, team 3, ant id 10, health 7, at (100, 120)
+//! >>> actions 0, 16 bytes
+//! 0000  05 09 05 …                 ant 0: 5, ant 1: 9, ant 2: 5, …
+//! ```
+//!
+//! Multi byte values are decoded as little endian.
+
+use std::{io::{self, Write}, fs::File, path::Path};
+
+use crate::network::{TurnView, TeamName};
+
+/// Number of bytes that is shown per line when the frame can not be decoded
+const RAW_LINE_LEN: usize = 16;
+
+/// Width of the column containing the hex bytes, wide enough for the 20 bytes of a team
+const HEX_WIDTH: usize = 20 * 3;
+
+/// Writes annotated hex dumps of the received frames and sent actions.
+#[derive(Debug)]
+pub struct Tracer<W: Write> {
+    output: W,
+    /// Number of frames that have been traced
+    frames: u64,
+}
+
+impl Tracer<io::BufWriter<File>> {
+
+    /// Creates the trace file at `path`.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(io::BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Tracer<W> {
+
+    /// Creates a tracer that writes into `output`.
+    pub fn new(output: W) -> Self {
+        Self { output, frames: 0 }
+    }
+
+    /// Traces the registration that is sent to the server.
+    pub fn register(&mut self, bytes: &[u8; 18]) -> io::Result<()> {
+        writeln!(self.output, ">>> register, {} bytes", bytes.len())?;
+        self.line(0, &bytes[0..2], &format!("client type: {}", u16::from_le_bytes([bytes[0], bytes[1]])))?;
+        self.line(2, &bytes[2..], &format!("name {:?}", TeamName::from_bytes(&bytes[2..]).to_string()))?;
+        self.output.flush()
+    }
+
+    /// Traces a frame that was received from the server at `timestamp`.
+    ///
+    /// Frames that can not be decoded are dumped without annotations.
+    pub fn frame(&mut self, timestamp: u64, frame: &[u8]) -> io::Result<()> {
+        writeln!(self.output, "<<< frame {} at {}, {} bytes", self.frames, timestamp, frame.len())?;
+        self.frames += 1;
+        let view = match TurnView::new(frame) {
+            Ok(view) => view,
+            Err(e) => {
+                writeln!(self.output, "not decoded: {}", e)?;
+                for (nr, bytes) in frame.chunks(RAW_LINE_LEN).enumerate() {
+                    self.line(nr * RAW_LINE_LEN, bytes, "")?;
+                }
+                return self.output.flush();
+            }
+        };
+        let team_id = view.team_id();
+        self.line(0, &frame[0..2], &format!("team id: {} (big endian: {})", team_id, i16::from_be_bytes([frame[0], frame[1]])))?;
+        let mut offset = 2;
+        for team in view.teams() {
+            let len = 4 + team.name_bytes().len();
+            self.line(offset, &frame[offset..offset + len], &format!("team {}: points {}, remaining ants {}, name {:?}",
+                team.id(), team.points(), team.remaining_ants(), TeamName::from_bytes(team.name_bytes()).to_string()))?;
+            offset += len;
+        }
+        self.line(offset, &frame[offset..offset + 2], &format!("number of objects: {}", view.nr_of_objects()))?;
+        offset += 2;
+        for (nr, object) in view.objects().enumerate() {
+            self.line(offset, &frame[offset..offset + 6], &format!("object {}: type {}, team {}, ant id {}, health {}, at ({}, {})",
+                nr, object.b1.upper, object.b1.lower, object.b2.upper, object.b2.lower, object.pos.0, object.pos.1))?;
+            offset += 6;
+        }
+        self.output.flush()
+    }
+
+    /// Traces the actions that were sent as answer to the last frame.
+    pub fn actions(&mut self, actions: &[u8; 16]) -> io::Result<()> {
+        writeln!(self.output, ">>> actions {}, {} bytes", self.frames.saturating_sub(1), actions.len())?;
+        let annotation = actions.iter().enumerate()
+            .map(|(id, action)| format!("ant {}: {}", id, action))
+            .collect::<Vec<String>>()
+            .join(", ");
+        self.line(0, actions, &annotation)?;
+        self.output.flush()
+    }
+
+    /// Writes a single line with the offset, the bytes and their annotation.
+    fn line(&mut self, offset: usize, bytes: &[u8], annotation: &str) -> io::Result<()> {
+        let hex = bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(" ");
+        let line = format!("{:04x}  {:<width$}  {}", offset, hex, annotation, width = HEX_WIDTH);
+        writeln!(self.output, "{}", line.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Turn, Object, Pair, network::TeamName};
+
+    use super::Tracer;
+
+    #[test]
+    fn test_trace() {
+        let turn = Turn::test(3, vec![Object { b1: Pair { upper: 1, lower: 3 }, b2: Pair { upper: 10, lower: 7 }, pos: (100, 120) }]).with_teams(|team| {
+            team.points = 5;
+            team.team_name = TeamName::new("Rust_pirates").unwrap();
+        });
+        let mut tracer = Tracer::new(Vec::new());
+        tracer.frame(1676000000000, &turn.to_bytes()).unwrap();
+        let mut actions = [5; 16];
+        actions[1] = 9;
+        tracer.actions(&actions).unwrap();
+        tracer.frame(1676000000100, &[3, 0, 1]).unwrap();
+        let trace = String::from_utf8(tracer.output).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+
+        assert_eq!(lines[0], "<<< frame 0 at 1676000000000, 330 bytes");
+        assert!(lines[1].starts_with("0000  03 00 "));
+        assert!(lines[1].ends_with("  team id: 3 (big endian: 768)"));
+        assert!(lines[2].starts_with("0002  05 00 10 00 52 75 73 74 5f 70 69 72 61 74 65 73 00 00 00 00  "));
+        assert!(lines[2].ends_with("team 0: points 5, remaining ants 16, name \"Rust_pirates\""));
+        assert!(lines[18].starts_with("0142  01 00 "));
+        assert!(lines[18].ends_with("number of objects: 1"));
+        assert!(lines[19].starts_with("0144  13 a7 64 00 78 00 "));
+        assert!(lines[19].ends_with("object 0: type 1, team 3, ant id 10, health 7, at (100, 120)"));
+        assert_eq!(lines[20], ">>> actions 0, 16 bytes");
+        assert!(lines[21].starts_with("0000  05 09 05"));
+        assert!(lines[21].contains("ant 0: 5, ant 1: 9, ant 2: 5"));
+        assert_eq!(lines[22], "<<< frame 1 at 1676000000100, 3 bytes");
+        assert_eq!(lines[23], "not decoded: unexpected end of input");
+        assert_eq!(lines[24], "0000  03 00 01");
+        assert_eq!(lines.len(), 25);
+    }
+}
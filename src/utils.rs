@@ -1,15 +1,16 @@
-use std::{io::{Bytes, BufReader}, net::TcpStream};
-
 /// Returns the point that will be reached from origin by going in the direction
+/// 
+/// Coordinates stay at 0 when going over the upper or left border of the board,
+/// going over the right or lower border leads to a point outside of the board.
 pub fn next_point(origin: (u16, u16), direction: u8) -> (u16, u16) {
     match direction {
-        1 => (origin.0 - 1, origin.1 - 1),
-        2 => (origin.0, origin.1 - 1),
-        3 => (origin.0 + 1, origin.1 - 1),
-        4 => (origin.0 - 1, origin.1),
+        1 => (origin.0.saturating_sub(1), origin.1.saturating_sub(1)),
+        2 => (origin.0, origin.1.saturating_sub(1)),
+        3 => (origin.0 + 1, origin.1.saturating_sub(1)),
+        4 => (origin.0.saturating_sub(1), origin.1),
         5 => origin,
         6 => (origin.0 + 1, origin.1),
-        7 => (origin.0 - 1, origin.1 + 1),
+        7 => (origin.0.saturating_sub(1), origin.1 + 1),
         8 => (origin.0, origin.1 + 1),
         9 => (origin.0 + 1, origin.1 + 1),
         _ => panic!("Invalid direction value"),
@@ -18,37 +19,37 @@ pub fn next_point(origin: (u16, u16), direction: u8) -> (u16, u16) {
 
 /// Calculates the distance between two points
 pub fn get_distance(pos1: (u16, u16), pos2: (u16, u16)) -> u16 {
-    let x_diff = (pos1.0 as i32 - pos2.0 as i32).abs() as u32;
-    let y_diff = (pos1.1 as i32 - pos2.1 as i32).abs() as u32;
+    let x_diff = (pos1.0 as i32 - pos2.0 as i32).unsigned_abs();
+    let y_diff = (pos1.1 as i32 - pos2.1 as i32).unsigned_abs();
     let distance_squared = x_diff * x_diff + y_diff * y_diff;
     (distance_squared as f64).sqrt() as u16
 }
 
-/// Takes two bytes from the iterator and returns them as array.
+/// Reads a little endian `u16` from the first two bytes of the slice.
 /// 
 /// # Panics
-/// Panics when the iterator does not contains two elements or when the tcp stream contains errored elements.
-pub fn read_to_two_byte_array(input: &mut Bytes<BufReader<TcpStream>>) -> Result<[u8; 2], String> {
-    let mut bytes: [u8; 2] = [0u8; 2];
-    for i in &mut bytes {
-        let buf = input.next();
-        if buf.is_none() {
-            return Err(String::from("Too few elements!"));
-        }
-        let buf = buf.unwrap();
-        if buf.is_err() {
-            return Err(format!("{:?}", buf.err()));
-        }
-        *i = buf.unwrap();
-    }
-    Ok(bytes)
+/// Panics when the slice contains less than two bytes.
+pub fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
 }
 
-/// Takes 16 byte from the iterator and parses tham as a string.
-pub fn bytes_to_string(input: &mut Bytes<BufReader<TcpStream>>) -> String {
-    let mut s = String::new();
-    for _i in 0..16 {
-        s.push(input.next().unwrap().unwrap() as char);
+#[cfg(test)]
+mod tests {
+    use crate::game::BOARD_SIZE;
+
+    use super::next_point;
+
+    #[test]
+    fn test_next_point() {
+        assert_eq!(next_point((5, 5), 1), (4, 4));
+        assert_eq!(next_point((5, 5), 5), (5, 5));
+        assert_eq!(next_point((5, 5), 9), (6, 6));
+        assert_eq!(next_point((0, 0), 1), (0, 0));
+        assert_eq!(next_point((0, 5), 1), (0, 4));
+        assert_eq!(next_point((5, 0), 3), (6, 0));
+        assert_eq!(next_point((0, 0), 7), (0, 1));
+        let edge = BOARD_SIZE - 1;
+        assert_eq!(next_point((edge, edge), 9), (BOARD_SIZE, BOARD_SIZE));
+        assert_eq!(next_point((edge, edge), 1), (edge - 1, edge - 1));
     }
-    s
 }
@@ -0,0 +1,104 @@
+use std::{collections::HashSet, fmt::{self, Display}};
+
+use crate::{Turn, ObjectKind, game::{BOARD_SIZE, MAX_HEALTH}};
+
+/// Inconsistent data found in a decoded turn.
+///
+/// Objects are identified by their index in the frame.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The object is not placed on the board.
+    OutsideOfBoard { object: usize, pos: (u16, u16) },
+    /// The type of the object is not known.
+    UnknownKind { object: usize, value: u8 },
+    /// The health of the ant is above the health of a new ant.
+    Health { object: usize, health: u8 },
+    /// The team has more than one ant with this id.
+    DuplicateAnt { team: u8, ant_id: u8 },
+    /// The number of living ants of the team differs from the remaining ants the team reports.
+    AntCount { team: u8, remaining_ants: u16, found: usize },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::OutsideOfBoard { object, pos } => write!(f, "object {} at {:?} is outside of the board", object, pos),
+            Violation::UnknownKind { object, value } => write!(f, "object {} has unknown type {}", object, value),
+            Violation::Health { object, health } => write!(f, "ant {} has health {}, at most {} is possible", object, health, MAX_HEALTH),
+            Violation::DuplicateAnt { team, ant_id } => write!(f, "team {} has more than one ant with id {}", team, ant_id),
+            Violation::AntCount { team, remaining_ants, found } => write!(f, "team {} has {} remaining ants but {} living ants were found", team, remaining_ants, found),
+        }
+    }
+}
+
+/// Checks the turn for data that can not occur in a valid game.
+///
+/// The team id of the client and the number of teams are already checked while decoding the frame.
+/// Returns all violations that were found, the turn is consistent if the list is empty.
+pub fn validate(turn: &Turn) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut ants = HashSet::new();
+    let mut living_ants = [0usize; 16];
+    for (index, object) in turn.objects.iter().enumerate() {
+        if object.pos.0 >= BOARD_SIZE || object.pos.1 >= BOARD_SIZE {
+            violations.push(Violation::OutsideOfBoard { object: index, pos: object.pos });
+        }
+        let kind = object.kind();
+        if let ObjectKind::Unknown(value) = kind {
+            violations.push(Violation::UnknownKind { object: index, value });
+        }
+        if !kind.is_ant() {
+            continue;
+        }
+        // The team id is a 4 bit value and therefore always a valid team
+        let (team, ant_id, health) = (object.b1.lower, object.b2.upper, object.b2.lower);
+        if health > MAX_HEALTH {
+            violations.push(Violation::Health { object: index, health });
+        }
+        if !ants.insert((team, ant_id)) {
+            violations.push(Violation::DuplicateAnt { team, ant_id });
+        }
+        if health > 0 {
+            living_ants[usize::from(team)] += 1;
+        }
+    }
+    for team in &turn.teams {
+        let found = living_ants.get(team.id as usize).copied().unwrap_or(0);
+        if usize::from(team.remaining_ants) != found {
+            violations.push(Violation::AntCount { team: team.id as u8, remaining_ants: team.remaining_ants, found });
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Turn, Object, Pair};
+
+    use super::{validate, Violation};
+
+    #[test]
+    fn test_validate() {
+        let mut remaining_ants = [0; 16];
+        remaining_ants[2] = 2;
+        let mut turn = Turn::test(2, vec![
+            Object { b1: Pair { upper: 1, lower: 2 }, b2: Pair { upper: 0, lower: 10 }, pos: (100, 100) },
+            Object { b1: Pair { upper: 3, lower: 2 }, b2: Pair { upper: 1, lower: 4 }, pos: (999, 0) },
+            // Dead ants are not counted as remaining ants
+            Object { b1: Pair { upper: 1, lower: 5 }, b2: Pair { upper: 0, lower: 0 }, pos: (500, 500) },
+            Object { b1: Pair { upper: 2, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (400, 400) },
+        ]).with_teams(|team| team.remaining_ants = remaining_ants[team.id as usize]);
+        assert_eq!(validate(&turn), Vec::new());
+
+        turn.push_object(Object { b1: Pair { upper: 5, lower: 2 }, b2: Pair { upper: 1, lower: 11 }, pos: (1000, 20) });
+        turn.push_object(Object { b1: Pair { upper: 7, lower: 0 }, b2: Pair { upper: 0, lower: 0 }, pos: (20, 20) });
+        assert_eq!(validate(&turn), vec![
+            Violation::OutsideOfBoard { object: 4, pos: (1000, 20) },
+            Violation::Health { object: 4, health: 11 },
+            Violation::DuplicateAnt { team: 2, ant_id: 1 },
+            Violation::UnknownKind { object: 5, value: 7 },
+            Violation::AntCount { team: 2, remaining_ants: 2, found: 3 },
+        ]);
+        assert_eq!(Violation::AntCount { team: 2, remaining_ants: 2, found: 3 }.to_string(), "team 2 has 2 remaining ants but 3 living ants were found");
+    }
+}